@@ -0,0 +1,172 @@
+use core::{
+    fmt::Debug,
+    mem::MaybeUninit,
+    ops::{Deref, DerefMut},
+};
+
+use crate::{partition_slice, List};
+
+/// A list of at most `N` elements that never allocates.
+pub struct ArrayList<T, const N: usize> {
+    buf: [MaybeUninit<T>; N],
+    len: usize,
+}
+
+impl<T, const N: usize> ArrayList<T, N> {
+    pub const fn new() -> Self {
+        ArrayList {
+            buf: [const { MaybeUninit::uninit() }; N],
+            len: 0,
+        }
+    }
+
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len == N
+    }
+
+    /// # Panics
+    /// Panics if the list is full.
+    pub fn push(&mut self, elem: T) {
+        if self.try_push(elem).is_err() {
+            panic!("ArrayList is full");
+        }
+    }
+
+    /// Gives `elem` back if the list is full.
+    pub fn try_push(&mut self, elem: T) -> Result<(), T> {
+        if self.is_full() {
+            return Err(elem);
+        }
+        self.buf[self.len].write(elem);
+        self.len += 1;
+        Ok(())
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        if self.len == 0 {
+            None
+        } else {
+            self.len -= 1;
+            Some(unsafe { self.buf[self.len].assume_init_read() })
+        }
+    }
+
+    pub fn clear(&mut self) {
+        while self.pop().is_some() {}
+    }
+}
+
+impl<T, const N: usize> Default for ArrayList<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> Drop for ArrayList<T, N> {
+    fn drop(&mut self) {
+        self.clear()
+    }
+}
+
+impl<T, const N: usize> Deref for ArrayList<T, N> {
+    type Target = [T];
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { core::slice::from_raw_parts(self.buf.as_ptr().cast(), self.len) }
+    }
+}
+
+impl<T, const N: usize> DerefMut for ArrayList<T, N> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { core::slice::from_raw_parts_mut(self.buf.as_mut_ptr().cast(), self.len) }
+    }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a ArrayList<T, N> {
+    type Item = &'a T;
+
+    type IntoIter = core::slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T, const N: usize> List<T> for ArrayList<T, N> {
+    #[inline]
+    fn push(&mut self, elem: T) {
+        ArrayList::push(self, elem)
+    }
+
+    fn partition(mut self) -> Self
+    where
+        T: PartialOrd,
+    {
+        partition_slice(&mut self);
+        self
+    }
+}
+
+/// # Panics
+/// Panics if the iterator yields more than `N` elements.
+impl<T, const N: usize> FromIterator<T> for ArrayList<T, N> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut l = Self::new();
+        for i in iter {
+            l.push(i);
+        }
+        l
+    }
+}
+
+impl<T: Debug, const N: usize> Debug for ArrayList<T, N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        for i in self.iter() {
+            write!(f, "{:?} ", i)?
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::rc::Rc;
+
+    use super::*;
+
+    #[test]
+    fn test() {
+        let mut l = ArrayList::<i32, 4>::new();
+        assert!(l.pop().is_none());
+        for i in 0..4 {
+            l.push(i);
+        }
+        assert!(l.is_full());
+        assert_eq!(l.try_push(4), Err(4));
+        assert_eq!(*l, [0, 1, 2, 3]);
+        assert_eq!(l.pop(), Some(3));
+        l[0] = 10;
+        assert!(l.iter().eq([10, 1, 2].iter()));
+
+        let rc = Rc::new(());
+        let l: ArrayList<_, 4> = (0..3).map(|_| rc.clone()).collect();
+        assert_eq!(Rc::strong_count(&rc), 4);
+        drop(l);
+        assert_eq!(Rc::strong_count(&rc), 1);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_overflow() {
+        let _: ArrayList<i32, 2> = (0..3).collect();
+    }
+
+    #[test]
+    fn test_partition() {
+        super::super::test::test_partition::<ArrayList<i32, 128>>();
+    }
+}
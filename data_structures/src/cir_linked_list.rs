@@ -1,12 +1,11 @@
-use std::{
-    alloc::{dealloc, Layout},
-    marker, ptr,
-};
+use allocator_api2::alloc::{handle_alloc_error, AllocError, Allocator, Global, Layout};
+use core::{marker, ptr};
 
 use crate::List;
 
-pub struct CirLinkedList<T> {
+pub struct CirLinkedList<T, A: Allocator = Global> {
     head: *mut Node<T>,
+    alloc: A,
     marker: marker::PhantomData<T>,
 }
 
@@ -18,12 +17,48 @@ struct Node<T> {
 
 impl<T> CirLinkedList<T> {
     pub fn new() -> Self {
+        Self::new_in(Global)
+    }
+}
+
+impl<T, A: Allocator> CirLinkedList<T, A> {
+    pub fn new_in(alloc: A) -> Self {
         CirLinkedList {
             head: ptr::null_mut(),
+            alloc,
             marker: marker::PhantomData,
         }
     }
 
+    pub fn allocator(&self) -> &A {
+        &self.alloc
+    }
+
+    pub fn push(&mut self, elem: T) {
+        if self.try_push(elem).is_err() {
+            handle_alloc_error(Layout::new::<Node<T>>())
+        }
+    }
+
+    /// Like `push`, but `elem` is dropped and an error returned instead of
+    /// aborting if no node can be allocated.
+    pub fn try_push(&mut self, elem: T) -> Result<(), AllocError> {
+        let node = self
+            .alloc
+            .allocate(Layout::new::<Node<T>>())?
+            .cast::<Node<T>>()
+            .as_ptr();
+        unsafe {
+            node.write(Node {
+                elem,
+                next: ptr::null_mut(),
+                prev: ptr::null_mut(),
+            });
+            self.push_node(node);
+        }
+        Ok(())
+    }
+
     pub fn pop(&mut self) -> Option<T> {
         if self.head.is_null() {
             None
@@ -37,14 +72,28 @@ impl<T> CirLinkedList<T> {
                 } else {
                     self.head = ptr::null_mut();
                 }
-                let elem = tail.read().elem;
-                dealloc(tail as *mut u8, Layout::new::<T>());
-                Some(elem)
+                Some(self.free_node(tail))
             }
         }
     }
 
-    fn iter(&self) -> Iter<T> {
+    pub fn last(&self) -> Option<&T> {
+        if self.head.is_null() {
+            None
+        } else {
+            unsafe { Some(&(*(*self.head).prev).elem) }
+        }
+    }
+
+    pub fn last_mut(&mut self) -> Option<&mut T> {
+        if self.head.is_null() {
+            None
+        } else {
+            unsafe { Some(&mut (*(*self.head).prev).elem) }
+        }
+    }
+
+    pub fn iter(&self) -> Iter<'_, T, A> {
         Iter {
             list: self,
             ptr: self.head,
@@ -52,13 +101,13 @@ impl<T> CirLinkedList<T> {
     }
 }
 
-impl<T> Default for CirLinkedList<T> {
+impl<T, A: Allocator + Default> Default for CirLinkedList<T, A> {
     fn default() -> Self {
-        Self::new()
+        Self::new_in(A::default())
     }
 }
 
-impl<T> Drop for CirLinkedList<T> {
+impl<T, A: Allocator> Drop for CirLinkedList<T, A> {
     fn drop(&mut self) {
         if !self.head.is_null() {
             let mut p = self.head;
@@ -66,8 +115,7 @@ impl<T> Drop for CirLinkedList<T> {
                 let free_this = p;
                 unsafe {
                     p = (*p).next;
-                    ptr::drop_in_place(p as *mut u8);
-                    dealloc(free_this as *mut u8, Layout::new::<Node<T>>())
+                    drop(self.free_node(free_this));
                 }
                 if p == self.head {
                     break;
@@ -77,9 +125,9 @@ impl<T> Drop for CirLinkedList<T> {
     }
 }
 
-impl<T> FromIterator<T> for CirLinkedList<T> {
+impl<T, A: Allocator + Default> FromIterator<T> for CirLinkedList<T, A> {
     fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
-        let mut l = Self::new();
+        let mut l = Self::default();
         for i in iter {
             l.push(i)
         }
@@ -87,10 +135,10 @@ impl<T> FromIterator<T> for CirLinkedList<T> {
     }
 }
 
-impl<'a, T> IntoIterator for &'a CirLinkedList<T> {
+impl<'a, T, A: Allocator> IntoIterator for &'a CirLinkedList<T, A> {
     type Item = &'a T;
 
-    type IntoIter = Iter<'a, T>;
+    type IntoIter = Iter<'a, T, A>;
 
     #[inline]
     fn into_iter(self) -> Self::IntoIter {
@@ -98,12 +146,12 @@ impl<'a, T> IntoIterator for &'a CirLinkedList<T> {
     }
 }
 
-pub struct Iter<'a, T> {
-    list: &'a CirLinkedList<T>,
+pub struct Iter<'a, T, A: Allocator = Global> {
+    list: &'a CirLinkedList<T, A>,
     ptr: *mut Node<T>,
 }
 
-impl<'a, T> Iterator for Iter<'a, T> {
+impl<'a, T, A: Allocator> Iterator for Iter<'a, T, A> {
     type Item = &'a T;
 
     fn next(&mut self) -> Option<Self::Item> {
@@ -122,7 +170,7 @@ impl<'a, T> Iterator for Iter<'a, T> {
     }
 }
 
-impl<'a, T> DoubleEndedIterator for Iter<'a, T> {
+impl<'a, T, A: Allocator> DoubleEndedIterator for Iter<'a, T, A> {
     fn next_back(&mut self) -> Option<Self::Item> {
         if self.list.head.is_null() {
             None
@@ -132,39 +180,18 @@ impl<'a, T> DoubleEndedIterator for Iter<'a, T> {
     }
 }
 
-impl<T> List<T> for CirLinkedList<T> {
+impl<T, A: Allocator + Clone + Default> List<T> for CirLinkedList<T, A> {
+    #[inline]
     fn push(&mut self, elem: T) {
-        if self.head.is_null() {
-            let mut new_node: *mut Node<T> = Box::into_raw(Box::new(Node {
-                elem,
-                next: ptr::null_mut(),
-                prev: ptr::null_mut(),
-            }));
-            unsafe {
-                (*new_node).next = new_node;
-                (*new_node).prev = new_node;
-            }
-            self.head = new_node;
-        } else {
-            let old_tail = unsafe { (*self.head).prev };
-            let new_node = Box::into_raw(Box::new(Node {
-                elem,
-                next: self.head,
-                prev: old_tail,
-            }));
-            unsafe {
-                (*old_tail).next = new_node;
-                (*self.head).prev = new_node;
-            }
-        }
+        CirLinkedList::push(self, elem)
     }
 
     fn partition(mut self) -> Self
     where
         T: PartialOrd,
     {
-        let mut geq = Self::new();
-        let mut le = Self::new();
+        let mut geq = Self::new_in(self.alloc.clone());
+        let mut le = Self::new_in(self.alloc.clone());
         let mut curr = self.head;
         if curr.is_null() {
             return self;
@@ -193,7 +220,7 @@ impl<T> List<T> for CirLinkedList<T> {
     }
 }
 
-impl<T> CirLinkedList<T> {
+impl<T, A: Allocator> CirLinkedList<T, A> {
     unsafe fn push_node(&mut self, node: *mut Node<T>) {
         let head = self.head;
         if head.is_null() {
@@ -207,7 +234,17 @@ impl<T> CirLinkedList<T> {
         (*node).next = self.head;
     }
 
-    fn concatenate(&mut self, mut list: CirLinkedList<T>) {
+    /// Takes the node's element and gives its memory back to the allocator.
+    unsafe fn free_node(&self, node: *mut Node<T>) -> T {
+        let elem = ptr::read(node).elem;
+        self.alloc.deallocate(
+            ptr::NonNull::new_unchecked(node).cast(),
+            Layout::new::<Node<T>>(),
+        );
+        elem
+    }
+
+    fn concatenate(&mut self, mut list: CirLinkedList<T, A>) {
         if list.head.is_null() {
             return;
         }
@@ -215,8 +252,12 @@ impl<T> CirLinkedList<T> {
         if !self.head.is_null() {
             let head = self.head;
             unsafe {
-                (*(*head).prev).next = list.head;
-                (*(*list.head).prev).next = self.head;
+                let tail = (*head).prev;
+                let list_tail = (*list.head).prev;
+                (*tail).next = list.head;
+                (*list.head).prev = tail;
+                (*list_tail).next = head;
+                (*head).prev = list_tail;
             }
         } else {
             self.head = list.head;
@@ -284,6 +325,24 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_allocator() {
+        let alloc = super::super::test::CountingAllocator::default();
+        let mut l = CirLinkedList::new_in(&alloc);
+        for i in 0..10 {
+            l.try_push(i).unwrap();
+        }
+        assert_eq!(alloc.live(), 10);
+        assert_eq!(l.pop(), Some(9));
+        assert_eq!(alloc.live(), 9);
+        drop(l);
+        assert_eq!(alloc.live(), 0);
+
+        let mut l = CirLinkedList::new_in(super::super::test::FailingAllocator);
+        assert_eq!(l.try_push(1), Err(AllocError));
+        assert!(l.last().is_none());
+    }
+
     #[test]
     fn test_push_node() {
         let mut l: CirLinkedList<i32> = CirLinkedList::new();
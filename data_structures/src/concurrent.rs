@@ -0,0 +1,645 @@
+//! Bounded lock-free containers that can be shared between threads.
+//!
+//! Both containers preallocate all their slots and never free one while
+//! they are alive, so a thread reading a slot another thread has just taken
+//! never touches freed memory. The model-checking tests run with
+//! `RUSTFLAGS="--cfg loom" cargo test -p data_structures --release concurrent`.
+
+use std::{
+    boxed::Box,
+    mem::MaybeUninit,
+    sync::{Condvar, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::queue::get_real_index;
+
+#[cfg(loom)]
+use loom::{
+    cell::UnsafeCell,
+    sync::atomic::{AtomicU64, AtomicUsize, Ordering},
+};
+#[cfg(not(loom))]
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+// Mirrors the part of loom's `UnsafeCell` we use.
+#[cfg(not(loom))]
+struct UnsafeCell<T>(std::cell::UnsafeCell<T>);
+
+#[cfg(not(loom))]
+impl<T> UnsafeCell<T> {
+    fn new(data: T) -> Self {
+        UnsafeCell(std::cell::UnsafeCell::new(data))
+    }
+
+    fn with_mut<R>(&self, f: impl FnOnce(*mut T) -> R) -> R {
+        f(self.0.get())
+    }
+}
+
+struct Slot<T> {
+    // `pos` when the slot is free to be written by the `pos`th push, and
+    // `pos + 1` once that push has finished.
+    seq: AtomicUsize,
+    elem: UnsafeCell<MaybeUninit<T>>,
+}
+
+/// A multi-producer multi-consumer queue on a ring buffer, after Dmitry
+/// Vyukov's bounded MPMC queue.
+pub struct ConcurrentQueue<T> {
+    slots: Box<[Slot<T>]>,
+    head: AtomicUsize,
+    tail: AtomicUsize,
+}
+
+unsafe impl<T: Send> Send for ConcurrentQueue<T> {}
+unsafe impl<T: Send> Sync for ConcurrentQueue<T> {}
+
+impl<T> ConcurrentQueue<T> {
+    /// The capacity is rounded up to a power of two.
+    pub fn with_capacity(capacity: usize) -> Self {
+        let capacity = capacity.max(2).next_power_of_two();
+        ConcurrentQueue {
+            slots: (0..capacity)
+                .map(|i| Slot {
+                    seq: AtomicUsize::new(i),
+                    elem: UnsafeCell::new(MaybeUninit::uninit()),
+                })
+                .collect(),
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Gives the element back if the queue is full.
+    pub fn push(&self, elem: T) -> Result<(), T> {
+        let mut pos = self.tail.load(Ordering::Relaxed);
+        loop {
+            let slot = &self.slots[get_real_index(pos, self.capacity())];
+            let seq = slot.seq.load(Ordering::Acquire);
+            let diff = seq.wrapping_sub(pos) as isize;
+            if diff == 0 {
+                match self.tail.compare_exchange_weak(
+                    pos,
+                    pos.wrapping_add(1),
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => {
+                        slot.elem.with_mut(|p| unsafe { (*p).write(elem) });
+                        slot.seq.store(pos.wrapping_add(1), Ordering::Release);
+                        return Ok(());
+                    }
+                    Err(current) => pos = current,
+                }
+            } else if diff < 0 {
+                return Err(elem);
+            } else {
+                pos = self.tail.load(Ordering::Relaxed);
+            }
+        }
+    }
+
+    pub fn pop(&self) -> Option<T> {
+        let mut pos = self.head.load(Ordering::Relaxed);
+        loop {
+            let slot = &self.slots[get_real_index(pos, self.capacity())];
+            let seq = slot.seq.load(Ordering::Acquire);
+            let diff = seq.wrapping_sub(pos.wrapping_add(1)) as isize;
+            if diff == 0 {
+                match self.head.compare_exchange_weak(
+                    pos,
+                    pos.wrapping_add(1),
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => {
+                        let elem = slot.elem.with_mut(|p| unsafe { (*p).assume_init_read() });
+                        slot.seq
+                            .store(pos.wrapping_add(self.capacity()), Ordering::Release);
+                        return Some(elem);
+                    }
+                    Err(current) => pos = current,
+                }
+            } else if diff < 0 {
+                return None;
+            } else {
+                pos = self.head.load(Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Only a snapshot, which may be stale by the time it is returned.
+    pub fn len(&self) -> usize {
+        // `head` never passes `tail`, so loading it first keeps the snapshot
+        // from going negative, unless the loads see stale values.
+        let head = self.head.load(Ordering::Acquire);
+        let tail = self.tail.load(Ordering::Acquire);
+        let len = tail.wrapping_sub(head) as isize;
+        if len <= 0 {
+            0
+        } else {
+            // Pushes after pops between the loads can take `tail` more than
+            // `capacity` past the `head` that was read.
+            (len as usize).min(self.capacity())
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T> Drop for ConcurrentQueue<T> {
+    fn drop(&mut self) {
+        while self.pop().is_some() {}
+    }
+}
+
+const NIL: u32 = u32::MAX;
+
+// A list head packs the index of the first node into the low half and a
+// counter bumped on every change into the high half, so that a node being
+// popped and pushed again between a load and a compare-exchange is noticed.
+#[inline]
+fn pack(index: u32, tag: u32) -> u64 {
+    (tag as u64) << 32 | index as u64
+}
+
+#[inline]
+fn unpack(head: u64) -> (u32, u32) {
+    (head as u32, (head >> 32) as u32)
+}
+
+struct Node<T> {
+    next: AtomicUsize,
+    elem: UnsafeCell<MaybeUninit<T>>,
+}
+
+/// A Treiber stack. Its nodes are kept in an array, with the unused ones on
+/// a second lock-free free list.
+pub struct ConcurrentStack<T> {
+    nodes: Box<[Node<T>]>,
+    head: AtomicU64,
+    free: AtomicU64,
+}
+
+unsafe impl<T: Send> Send for ConcurrentStack<T> {}
+unsafe impl<T: Send> Sync for ConcurrentStack<T> {}
+
+impl<T> ConcurrentStack<T> {
+    pub fn with_capacity(capacity: usize) -> Self {
+        assert!(capacity < NIL as usize, "Capacity too large");
+        ConcurrentStack {
+            nodes: (0..capacity)
+                .map(|i| Node {
+                    next: AtomicUsize::new(if i + 1 < capacity {
+                        i + 1
+                    } else {
+                        NIL as usize
+                    }),
+                    elem: UnsafeCell::new(MaybeUninit::uninit()),
+                })
+                .collect(),
+            head: AtomicU64::new(pack(NIL, 0)),
+            free: AtomicU64::new(pack(if capacity > 0 { 0 } else { NIL }, 0)),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Gives the element back if the stack is full.
+    pub fn push(&self, elem: T) -> Result<(), T> {
+        let index = match self.take_node(&self.free) {
+            Some(index) => index,
+            None => return Err(elem),
+        };
+        self.nodes[index as usize]
+            .elem
+            .with_mut(|p| unsafe { (*p).write(elem) });
+        self.put_node(&self.head, index);
+        Ok(())
+    }
+
+    pub fn pop(&self) -> Option<T> {
+        let index = self.take_node(&self.head)?;
+        let elem = self.nodes[index as usize]
+            .elem
+            .with_mut(|p| unsafe { (*p).assume_init_read() });
+        self.put_node(&self.free, index);
+        Some(elem)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        unpack(self.head.load(Ordering::Relaxed)).0 == NIL
+    }
+
+    fn take_node(&self, list: &AtomicU64) -> Option<u32> {
+        let mut head = list.load(Ordering::Acquire);
+        loop {
+            let (index, tag) = unpack(head);
+            if index == NIL {
+                return None;
+            }
+            // The node may be taken by another thread meanwhile, in which
+            // case `next` is garbage but the compare-exchange fails anyway.
+            let next = self.nodes[index as usize].next.load(Ordering::Relaxed) as u32;
+            match list.compare_exchange_weak(
+                head,
+                pack(next, tag.wrapping_add(1)),
+                Ordering::Acquire,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => return Some(index),
+                Err(current) => head = current,
+            }
+        }
+    }
+
+    fn put_node(&self, list: &AtomicU64, index: u32) {
+        let mut head = list.load(Ordering::Relaxed);
+        loop {
+            let (next, tag) = unpack(head);
+            self.nodes[index as usize]
+                .next
+                .store(next as usize, Ordering::Relaxed);
+            match list.compare_exchange_weak(
+                head,
+                pack(index, tag.wrapping_add(1)),
+                Ordering::Release,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return,
+                Err(current) => head = current,
+            }
+        }
+    }
+}
+
+impl<T> Drop for ConcurrentStack<T> {
+    fn drop(&mut self) {
+        while self.pop().is_some() {}
+    }
+}
+
+/// A [`ConcurrentQueue`] whose `push` and `pop` wait for room or for an
+/// element instead of failing.
+pub struct BlockingQueue<T> {
+    queue: ConcurrentQueue<T>,
+    lock: Mutex<()>,
+    not_empty: Condvar,
+    not_full: Condvar,
+}
+
+impl<T> BlockingQueue<T> {
+    pub fn with_capacity(capacity: usize) -> Self {
+        BlockingQueue {
+            queue: ConcurrentQueue::with_capacity(capacity),
+            lock: Mutex::new(()),
+            not_empty: Condvar::new(),
+            not_full: Condvar::new(),
+        }
+    }
+
+    pub fn try_push(&self, elem: T) -> Result<(), T> {
+        self.queue.push(elem)?;
+        self.wake(&self.not_empty);
+        Ok(())
+    }
+
+    pub fn try_pop(&self) -> Option<T> {
+        let elem = self.queue.pop()?;
+        self.wake(&self.not_full);
+        Some(elem)
+    }
+
+    pub fn push(&self, mut elem: T) {
+        let mut guard = self.lock.lock().unwrap();
+        loop {
+            match self.queue.push(elem) {
+                Ok(()) => break,
+                Err(e) => elem = e,
+            }
+            guard = self.not_full.wait(guard).unwrap();
+        }
+        drop(guard);
+        self.not_empty.notify_one();
+    }
+
+    pub fn pop(&self) -> T {
+        let mut guard = self.lock.lock().unwrap();
+        let elem = loop {
+            if let Some(elem) = self.queue.pop() {
+                break elem;
+            }
+            guard = self.not_empty.wait(guard).unwrap();
+        };
+        drop(guard);
+        self.not_full.notify_one();
+        elem
+    }
+
+    /// Returns `None` if no element arrives within `timeout`.
+    pub fn pop_timeout(&self, timeout: Duration) -> Option<T> {
+        let deadline = Instant::now() + timeout;
+        let mut guard = self.lock.lock().unwrap();
+        let elem = loop {
+            if let Some(elem) = self.queue.pop() {
+                break elem;
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return None;
+            }
+            guard = self
+                .not_empty
+                .wait_timeout(guard, deadline - now)
+                .unwrap()
+                .0;
+        };
+        drop(guard);
+        self.not_full.notify_one();
+        Some(elem)
+    }
+
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    // Waiters check the queue while holding the lock, so taking it here
+    // makes sure none of them misses the notification.
+    fn wake(&self, condvar: &Condvar) {
+        drop(self.lock.lock().unwrap());
+        condvar.notify_one();
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod test {
+    use std::{sync::Arc, thread};
+
+    use super::*;
+
+    const THREADS: usize = 4;
+    const PER_THREAD: usize = 10000;
+
+    #[test]
+    fn test_queue() {
+        let q = ConcurrentQueue::with_capacity(3);
+        assert_eq!(q.capacity(), 4);
+        for i in 0..4 {
+            q.push(i).unwrap();
+        }
+        assert_eq!(q.push(4), Err(4));
+        assert_eq!(q.len(), 4);
+        for i in 0..4 {
+            assert_eq!(q.pop(), Some(i));
+        }
+        assert!(q.pop().is_none());
+
+        let q = ConcurrentQueue::with_capacity(2);
+        q.push(Box::new(1)).unwrap();
+    }
+
+    #[test]
+    fn test_stack() {
+        let s = ConcurrentStack::with_capacity(3);
+        for i in 0..3 {
+            s.push(i).unwrap();
+        }
+        assert_eq!(s.push(3), Err(3));
+        for i in (0..3).rev() {
+            assert_eq!(s.pop(), Some(i));
+        }
+        assert!(s.pop().is_none());
+        assert!(s.is_empty());
+
+        let s = ConcurrentStack::with_capacity(2);
+        s.push(Box::new(1)).unwrap();
+
+        let s = ConcurrentStack::<i32>::with_capacity(0);
+        assert_eq!(s.push(1), Err(1));
+    }
+
+    /// Every element pushed by the producers is popped exactly once.
+    fn test_mpmc(push: impl Fn(usize) + Sync, pop: impl Fn() -> Option<usize> + Sync) {
+        let mut seen = vec![false; THREADS * PER_THREAD];
+        thread::scope(|s| {
+            for t in 0..THREADS {
+                let push = &push;
+                s.spawn(move || {
+                    for i in 0..PER_THREAD {
+                        push(t * PER_THREAD + i);
+                    }
+                });
+            }
+            let consumers: Vec<_> = (0..THREADS)
+                .map(|_| {
+                    s.spawn(|| {
+                        let mut got = Vec::new();
+                        while got.len() < PER_THREAD {
+                            match pop() {
+                                Some(i) => got.push(i),
+                                None => thread::yield_now(),
+                            }
+                        }
+                        got
+                    })
+                })
+                .collect();
+            for c in consumers {
+                for i in c.join().unwrap() {
+                    assert!(!seen[i]);
+                    seen[i] = true;
+                }
+            }
+        });
+        assert!(seen.into_iter().all(|b| b));
+    }
+
+    #[test]
+    fn test_queue_threads() {
+        let q = ConcurrentQueue::with_capacity(64);
+        test_mpmc(
+            |i| {
+                let mut elem = i;
+                while let Err(e) = q.push(elem) {
+                    elem = e;
+                    thread::yield_now();
+                }
+            },
+            || q.pop(),
+        );
+        assert!(q.is_empty());
+    }
+
+    #[test]
+    fn test_len_while_draining() {
+        let q = Arc::new(ConcurrentQueue::with_capacity(4));
+        let worker = {
+            let q = Arc::clone(&q);
+            thread::spawn(move || {
+                for i in 0..PER_THREAD * 10 {
+                    q.push(i).unwrap();
+                    q.pop().unwrap();
+                }
+            })
+        };
+        while !worker.is_finished() {
+            assert!(q.len() <= 1);
+        }
+        worker.join().unwrap();
+        assert!(q.is_empty());
+    }
+
+    #[test]
+    fn test_stack_threads() {
+        let s = ConcurrentStack::with_capacity(64);
+        test_mpmc(
+            |i| {
+                let mut elem = i;
+                while let Err(e) = s.push(elem) {
+                    elem = e;
+                    thread::yield_now();
+                }
+            },
+            || s.pop(),
+        );
+        assert!(s.is_empty());
+    }
+
+    #[test]
+    fn test_blocking_queue() {
+        let q = BlockingQueue::with_capacity(2);
+        test_mpmc(|i| q.push(i), || Some(q.pop()));
+        assert!(q.is_empty());
+    }
+
+    #[test]
+    fn test_pop_timeout() {
+        let q = Arc::new(BlockingQueue::with_capacity(2));
+        assert!(q.pop_timeout(Duration::from_millis(10)).is_none());
+        assert!(q.try_pop().is_none());
+
+        let producer = {
+            let q = Arc::clone(&q);
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(10));
+                q.push(1);
+            })
+        };
+        assert_eq!(q.pop_timeout(Duration::from_secs(10)), Some(1));
+        producer.join().unwrap();
+
+        q.try_push(2).unwrap();
+        q.try_push(3).unwrap();
+        assert_eq!(q.try_push(4), Err(4));
+        assert_eq!(q.len(), 2);
+    }
+}
+
+#[cfg(all(test, loom))]
+mod loom_test {
+    use loom::{sync::Arc, thread};
+
+    use super::*;
+
+    #[test]
+    fn test_queue() {
+        loom::model(|| {
+            let q = Arc::new(ConcurrentQueue::with_capacity(2));
+            let producers: Vec<_> = (0..2)
+                .map(|i| {
+                    let q = Arc::clone(&q);
+                    thread::spawn(move || q.push(i).unwrap())
+                })
+                .collect();
+            let consumer = {
+                let q = Arc::clone(&q);
+                thread::spawn(move || q.pop())
+            };
+            for p in producers {
+                p.join().unwrap();
+            }
+            let mut got: Vec<i32> = consumer.join().unwrap().into_iter().collect();
+            while let Some(i) = q.pop() {
+                got.push(i);
+            }
+            got.sort_unstable();
+            assert_eq!(got, [0, 1]);
+        });
+    }
+
+    #[test]
+    fn test_len() {
+        loom::model(|| {
+            let q = Arc::new(ConcurrentQueue::with_capacity(2));
+            let worker = {
+                let q = Arc::clone(&q);
+                thread::spawn(move || {
+                    for i in 0..2 {
+                        q.push(i).unwrap();
+                        q.pop().unwrap();
+                    }
+                })
+            };
+            assert!(q.len() <= 1);
+            assert!(q.len() <= 1);
+            worker.join().unwrap();
+        });
+    }
+
+    #[test]
+    fn test_queue_wrap_around() {
+        loom::model(|| {
+            let q = Arc::new(ConcurrentQueue::with_capacity(2));
+            q.push(0).unwrap();
+            let consumer = {
+                let q = Arc::clone(&q);
+                thread::spawn(move || (q.pop(), q.pop()))
+            };
+            q.push(1).unwrap();
+            let _ = q.push(2);
+            let (a, b) = consumer.join().unwrap();
+            assert_eq!(a, Some(0));
+            assert!(matches!(b, None | Some(1)));
+        });
+    }
+
+    #[test]
+    fn test_stack() {
+        loom::model(|| {
+            let s = Arc::new(ConcurrentStack::with_capacity(2));
+            s.push(0).unwrap();
+            let threads: Vec<_> = (1..3)
+                .map(|i| {
+                    let s = Arc::clone(&s);
+                    thread::spawn(move || (s.pop(), s.push(i).is_ok().then_some(i)))
+                })
+                .collect();
+            let mut pushed = vec![0];
+            let mut popped = Vec::new();
+            for t in threads {
+                let (p, i) = t.join().unwrap();
+                popped.extend(p);
+                pushed.extend(i);
+            }
+            while let Some(i) = s.pop() {
+                popped.push(i);
+            }
+            pushed.sort_unstable();
+            popped.sort_unstable();
+            assert_eq!(pushed, popped);
+        });
+    }
+}
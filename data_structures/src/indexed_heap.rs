@@ -0,0 +1,429 @@
+use core::{fmt::Debug, marker::PhantomData};
+
+use crate::{
+    priority_queue::{HeapOrder, Max},
+    seq_list::SeqList,
+};
+
+/// Refers to an item pushed into an [`IndexedHeap`]. A handle becomes stale
+/// once its item leaves the heap and is never reused for another item: a slot
+/// is retired rather than reused once its generation runs out.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Handle {
+    slot: usize,
+    generation: usize,
+}
+
+struct Entry<T, P> {
+    item: T,
+    priority: P,
+    pos: usize,
+}
+
+struct Slot<T, P> {
+    entry: Option<Entry<T, P>>,
+    generation: usize,
+}
+
+/// A `D`-ary heap of items ordered by their priorities, which can be
+/// changed or removed through the handles returned by `push`.
+pub struct IndexedHeap<T, P, O = Max, const D: usize = 2> {
+    heap: SeqList<usize>,
+    slots: SeqList<Slot<T, P>>,
+    free: SeqList<usize>,
+    marker: PhantomData<O>,
+}
+
+pub type QuaternaryHeap<T, P, O = Max> = IndexedHeap<T, P, O, 4>;
+
+impl<T, P: Ord, O: HeapOrder, const D: usize> IndexedHeap<T, P, O, D> {
+    pub fn new() -> Self {
+        assert!(D >= 2, "A heap needs an arity of at least 2");
+        IndexedHeap {
+            heap: SeqList::new(),
+            slots: SeqList::new(),
+            free: SeqList::new(),
+            marker: PhantomData,
+        }
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        assert!(D >= 2, "A heap needs an arity of at least 2");
+        IndexedHeap {
+            heap: SeqList::with_capacity(capacity),
+            slots: SeqList::with_capacity(capacity),
+            free: SeqList::new(),
+            marker: PhantomData,
+        }
+    }
+
+    pub fn push(&mut self, item: T, priority: P) -> Handle {
+        let pos = self.heap.len();
+        let entry = Entry {
+            item,
+            priority,
+            pos,
+        };
+        let slot = match self.free.pop() {
+            Some(slot) => {
+                self.slots[slot].entry = Some(entry);
+                slot
+            }
+            None => {
+                self.slots.push(Slot {
+                    entry: Some(entry),
+                    generation: 0,
+                });
+                self.slots.len() - 1
+            }
+        };
+        self.heap.push(slot);
+        self.sift_up(pos);
+        Handle {
+            slot,
+            generation: self.slots[slot].generation,
+        }
+    }
+
+    pub fn pop(&mut self) -> Option<(T, P)> {
+        let slot = *self.heap.first()?;
+        Some(self.remove_at(0, slot))
+    }
+
+    pub fn peek(&self) -> Option<(&T, &P)> {
+        let entry = self.entry_at(0)?;
+        Some((&entry.item, &entry.priority))
+    }
+
+    pub fn peek_handle(&self) -> Option<Handle> {
+        let slot = *self.heap.first()?;
+        Some(Handle {
+            slot,
+            generation: self.slots[slot].generation,
+        })
+    }
+
+    pub fn contains(&self, handle: Handle) -> bool {
+        self.entry(handle).is_some()
+    }
+
+    pub fn get(&self, handle: Handle) -> Option<&T> {
+        Some(&self.entry(handle)?.item)
+    }
+
+    pub fn get_mut(&mut self, handle: Handle) -> Option<&mut T> {
+        Some(&mut self.entry_mut(handle)?.item)
+    }
+
+    pub fn priority(&self, handle: Handle) -> Option<&P> {
+        Some(&self.entry(handle)?.priority)
+    }
+
+    /// Returns the old priority, or `None` if the handle is stale.
+    pub fn change_priority(&mut self, handle: Handle, priority: P) -> Option<P> {
+        let entry = self.entry_mut(handle)?;
+        let pos = entry.pos;
+        let old = core::mem::replace(&mut entry.priority, priority);
+        self.sift_up(pos);
+        self.sift_down(self.slots[handle.slot].entry.as_ref().unwrap().pos);
+        Some(old)
+    }
+
+    pub fn remove(&mut self, handle: Handle) -> Option<(T, P)> {
+        let pos = self.entry(handle)?.pos;
+        Some(self.remove_at(pos, handle.slot))
+    }
+
+    pub fn iter(&self) -> Iter<'_, T, P> {
+        Iter {
+            heap: self.heap.iter(),
+            slots: &self.slots,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        while let Some(slot) = self.heap.pop() {
+            self.vacate(slot);
+        }
+    }
+
+    fn entry(&self, handle: Handle) -> Option<&Entry<T, P>> {
+        let slot = self.slots.get(handle.slot)?;
+        if slot.generation == handle.generation {
+            slot.entry.as_ref()
+        } else {
+            None
+        }
+    }
+
+    fn entry_mut(&mut self, handle: Handle) -> Option<&mut Entry<T, P>> {
+        let slot = self.slots.get_mut(handle.slot)?;
+        if slot.generation == handle.generation {
+            slot.entry.as_mut()
+        } else {
+            None
+        }
+    }
+
+    fn entry_at(&self, pos: usize) -> Option<&Entry<T, P>> {
+        self.slots[*self.heap.get(pos)?].entry.as_ref()
+    }
+
+    fn priority_at(&self, pos: usize) -> &P {
+        &self.entry_at(pos).unwrap().priority
+    }
+
+    fn remove_at(&mut self, pos: usize, slot: usize) -> (T, P) {
+        let last = self.heap.len() - 1;
+        self.swap(pos, last);
+        self.heap.pop();
+        if pos < last {
+            self.sift_up(pos);
+            self.sift_down(pos);
+        }
+        let entry = self.vacate(slot);
+        (entry.item, entry.priority)
+    }
+
+    fn vacate(&mut self, slot: usize) -> Entry<T, P> {
+        let slot_ref = &mut self.slots[slot];
+        let entry = slot_ref.entry.take().unwrap();
+        // A slot whose generation would wrap is retired instead.
+        if let Some(generation) = slot_ref.generation.checked_add(1) {
+            slot_ref.generation = generation;
+            self.free.push(slot);
+        }
+        entry
+    }
+
+    fn swap(&mut self, a: usize, b: usize) {
+        self.heap.swap(a, b);
+        for pos in [a, b] {
+            let slot = self.heap[pos];
+            self.slots[slot].entry.as_mut().unwrap().pos = pos;
+        }
+    }
+
+    fn sift_up(&mut self, mut pos: usize) {
+        while pos > 0 {
+            let parent = (pos - 1) / D;
+            if !O::precedes(self.priority_at(pos), self.priority_at(parent)) {
+                break;
+            }
+            self.swap(pos, parent);
+            pos = parent;
+        }
+    }
+
+    fn sift_down(&mut self, mut pos: usize) {
+        let len = self.heap.len();
+        loop {
+            let first_child = D * pos + 1;
+            if first_child >= len {
+                break;
+            }
+            let last_child = (first_child + D).min(len);
+            let child = (first_child + 1..last_child).fold(first_child, |best, child| {
+                if O::precedes(self.priority_at(child), self.priority_at(best)) {
+                    child
+                } else {
+                    best
+                }
+            });
+            if !O::precedes(self.priority_at(child), self.priority_at(pos)) {
+                break;
+            }
+            self.swap(pos, child);
+            pos = child;
+        }
+    }
+}
+
+impl<T, P: Ord, O: HeapOrder, const D: usize> Default for IndexedHeap<T, P, O, D> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct Iter<'a, T, P> {
+    heap: core::slice::Iter<'a, usize>,
+    slots: &'a SeqList<Slot<T, P>>,
+}
+
+impl<'a, T, P> Iterator for Iter<'a, T, P> {
+    type Item = (Handle, &'a T, &'a P);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let slot = *self.heap.next()?;
+        let slot_ref = &self.slots[slot];
+        let entry = slot_ref.entry.as_ref().unwrap();
+        let handle = Handle {
+            slot,
+            generation: slot_ref.generation,
+        };
+        Some((handle, &entry.item, &entry.priority))
+    }
+}
+
+impl<'a, T, P: Ord, O: HeapOrder, const D: usize> IntoIterator for &'a IndexedHeap<T, P, O, D> {
+    type Item = (Handle, &'a T, &'a P);
+
+    type IntoIter = Iter<'a, T, P>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T: Debug, P: Debug, O, const D: usize> Debug for IndexedHeap<T, P, O, D> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        for &slot in self.heap.iter() {
+            let entry = self.slots[slot].entry.as_ref().unwrap();
+            write!(f, "{:?}: {:?} ", entry.item, entry.priority)?
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::priority_queue::Min;
+    use rand::Rng;
+
+    /// Runs random operations against both the heap and a `BTreeMap` keyed
+    /// by `(priority, item)`, checking that they always agree.
+    fn test_against_model<O: HeapOrder, const D: usize>(is_max: bool) {
+        let mut rng = rand::thread_rng();
+        let mut heap = IndexedHeap::<usize, i32, O, D>::new();
+        let mut model = BTreeMap::<(i32, usize), ()>::new();
+        let mut live: Vec<(Handle, usize)> = Vec::new();
+        let mut dead: Vec<Handle> = Vec::new();
+        let mut next_item = 0;
+
+        for _ in 0..5000 {
+            match rng.gen_range(0..6) {
+                0 | 1 => {
+                    let priority = rng.gen_range(-50..50);
+                    let handle = heap.push(next_item, priority);
+                    model.insert((priority, next_item), ());
+                    live.push((handle, next_item));
+                    next_item += 1;
+                }
+                2 => {
+                    let expect = if is_max {
+                        model.keys().next_back()
+                    } else {
+                        model.keys().next()
+                    };
+                    assert_eq!(heap.peek().map(|(_, p)| *p), expect.map(|k| k.0));
+                    match heap.pop() {
+                        Some((item, priority)) => {
+                            assert!(model.remove(&(priority, item)).is_some());
+                            let i = live.iter().position(|l| l.1 == item).unwrap();
+                            dead.push(live.swap_remove(i).0);
+                        }
+                        None => assert!(model.is_empty()),
+                    }
+                }
+                3 | 4 if !live.is_empty() => {
+                    let (handle, item) = live[rng.gen_range(0..live.len())];
+                    let priority = rng.gen_range(-50..50);
+                    let old = heap.change_priority(handle, priority).unwrap();
+                    model.remove(&(old, item)).unwrap();
+                    model.insert((priority, item), ());
+                    assert_eq!(heap.priority(handle), Some(&priority));
+                }
+                5 if !live.is_empty() => {
+                    let i = rng.gen_range(0..live.len());
+                    let (handle, item) = live.swap_remove(i);
+                    let (removed, priority) = heap.remove(handle).unwrap();
+                    assert_eq!(removed, item);
+                    assert!(model.remove(&(priority, item)).is_some());
+                    dead.push(handle);
+                }
+                _ => {}
+            }
+            assert_eq!(heap.len(), model.len());
+        }
+
+        for handle in dead {
+            assert!(!heap.contains(handle));
+            assert!(heap.remove(handle).is_none());
+            assert!(heap.change_priority(handle, 0).is_none());
+        }
+        assert_eq!(heap.iter().count(), model.len());
+        for (handle, item) in live {
+            assert_eq!(heap.get(handle), Some(&item));
+        }
+
+        let mut popped = Vec::new();
+        while let Some((_, priority)) = heap.pop() {
+            popped.push(priority);
+        }
+        let mut expect: Vec<i32> = model.keys().map(|k| k.0).collect();
+        if is_max {
+            expect.reverse();
+        }
+        assert_eq!(popped, expect);
+    }
+
+    #[test]
+    fn test_binary() {
+        test_against_model::<Max, 2>(true);
+        test_against_model::<Min, 2>(false);
+    }
+
+    #[test]
+    fn test_d_ary() {
+        test_against_model::<Max, 3>(true);
+        test_against_model::<Min, 4>(false);
+        test_against_model::<Max, 8>(true);
+    }
+
+    #[test]
+    fn test_decrease_key() {
+        let mut heap = QuaternaryHeap::<&str, u32, Min>::new();
+        let a = heap.push("a", 10);
+        let b = heap.push("b", 20);
+        let c = heap.push("c", 30);
+        assert_eq!(heap.peek(), Some((&"a", &10)));
+        assert_eq!(heap.change_priority(c, 5), Some(30));
+        assert_eq!(heap.peek_handle(), Some(c));
+        assert_eq!(heap.remove(a), Some(("a", 10)));
+        assert_eq!(heap.get(a), None);
+        *heap.get_mut(b).unwrap() = "d";
+        assert_eq!(heap.pop(), Some(("c", 5)));
+        assert_eq!(heap.pop(), Some(("d", 20)));
+        assert!(heap.is_empty());
+
+        let d = heap.push("e", 1);
+        assert_ne!(d, a);
+        assert!(!heap.contains(a));
+        heap.clear();
+        assert!(!heap.contains(d));
+    }
+
+    #[test]
+    fn test_retired_slot() {
+        let mut heap = IndexedHeap::<u32, u32>::new();
+        let a = heap.push(1, 1);
+        heap.slots[a.slot].generation = usize::MAX;
+        let a = heap.peek_handle().unwrap();
+        assert_eq!(heap.remove(a), Some((1, 1)));
+        let b = heap.push(2, 2);
+        assert_ne!(b.slot, a.slot);
+        assert!(!heap.contains(a));
+        assert_eq!(heap.get(b), Some(&2));
+    }
+}
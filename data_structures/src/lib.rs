@@ -1,9 +1,42 @@
-#![feature(iter_is_partitioned)]
+#![cfg_attr(not(test), no_std)]
+#![cfg_attr(
+    feature = "nightly",
+    feature(allocator_api, box_into_inner, trusted_len)
+)]
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+use core::ptr;
+
+pub mod array_list;
+#[cfg(feature = "alloc")]
 pub mod cir_linked_list;
+#[cfg(feature = "std")]
+pub mod concurrent;
+#[cfg(feature = "alloc")]
+pub mod indexed_heap;
+#[cfg(feature = "alloc")]
 pub mod linked_list;
+#[cfg(feature = "alloc")]
+pub mod persistent;
+#[cfg(feature = "alloc")]
+pub mod priority_queue;
 pub mod queue;
+#[cfg(feature = "rayon")]
+mod rayon_impls;
+#[cfg(feature = "alloc")]
 pub mod seq_list;
+#[cfg(feature = "serde")]
+mod serde_impls;
+#[cfg(feature = "alloc")]
+pub mod slab_list;
+#[cfg(feature = "alloc")]
+pub mod small_seq_list;
+#[cfg(feature = "snapshot")]
+pub mod snapshot;
 pub mod stack;
 
 pub trait List<T>: FromIterator<T>
@@ -17,10 +50,119 @@ where
         T: PartialOrd;
 }
 
+/// Moves the elements less than the first one in front of it and the rest
+/// behind it.
+pub(crate) fn partition_slice<T: PartialOrd>(slice: &mut [T]) {
+    if slice.is_empty() {
+        return;
+    }
+    let base = slice.as_mut_ptr();
+    unsafe {
+        let mut l = base.add(1);
+        let mut r = base.add(slice.len() - 1);
+        let first = &*base;
+
+        loop {
+            if l.offset_from(r) > 0 {
+                ptr::swap(base, r);
+                return;
+            }
+            if *l < *first {
+                l = l.add(1);
+            } else {
+                break;
+            }
+        }
+
+        loop {
+            if r.offset_from(base) <= 0 {
+                return;
+            }
+            if *r >= *first {
+                r = r.sub(1);
+            } else {
+                break;
+            }
+        }
+
+        loop {
+            while *l < *first {
+                l = l.add(1)
+            }
+
+            while *r >= *first {
+                r = r.sub(1)
+            }
+
+            if l >= r {
+                break;
+            }
+            ptr::swap(l, r);
+            l = l.add(1);
+            r = r.sub(1);
+        }
+        ptr::swap(base, r);
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    #[cfg(feature = "alloc")]
+    use allocator_api2::alloc::{AllocError, Allocator, Global, Layout};
     use rand::Rng;
+    #[cfg(feature = "alloc")]
+    use std::{cell::Cell, ptr::NonNull};
+
+    /// Keeps count of the blocks it has handed out and not got back.
+    #[cfg(feature = "alloc")]
+    #[derive(Default)]
+    pub struct CountingAllocator {
+        live: Cell<usize>,
+    }
+
+    #[cfg(feature = "alloc")]
+    impl CountingAllocator {
+        pub fn live(&self) -> usize {
+            self.live.get()
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    unsafe impl Allocator for CountingAllocator {
+        fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+            let ptr = Global.allocate(layout)?;
+            self.live.set(self.live.get() + 1);
+            Ok(ptr)
+        }
+
+        unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+            self.live.set(self.live.get() - 1);
+            Global.deallocate(ptr, layout)
+        }
+    }
+
+    /// Fails every allocation.
+    #[cfg(feature = "alloc")]
+    #[derive(Default, Clone)]
+    pub struct FailingAllocator;
+
+    #[cfg(feature = "alloc")]
+    unsafe impl Allocator for FailingAllocator {
+        fn allocate(&self, _layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+            Err(AllocError)
+        }
+
+        unsafe fn deallocate(&self, _ptr: NonNull<u8>, _layout: Layout) {
+            unreachable!()
+        }
+    }
+
+    /// Whether all the elements satisfying `pred` come before the others.
+    pub fn is_partitioned<I: IntoIterator>(iter: I, mut pred: impl FnMut(I::Item) -> bool) -> bool {
+        let mut iter = iter.into_iter();
+        iter.all(&mut pred) || iter.all(|x| !pred(x))
+    }
 
     pub fn test_partition<L: List<i32>>()
     where
@@ -53,12 +195,12 @@ mod test {
         let errors = vecs
             .into_iter()
             .map(|v| -> Result<(), (Vec<i32>, L)> {
-                let first = *v.get(0).unwrap_or(&0);
+                let first = *v.first().unwrap_or(&0);
                 let mut l: L = v.clone().into_iter().collect();
                 l = l.partition();
                 let len = l.into_iter().count();
-                (v.len() == len && l.into_iter().is_partitioned(|i| i < &first))
-                    .then(|| ())
+                (v.len() == len && is_partitioned(&l, |i| i < &first))
+                    .then_some(())
                     .ok_or((v, l))
             })
             .filter_map(Result::err)
@@ -1,36 +1,74 @@
 use super::List;
-use std::ptr;
-
-type Link<T> = Option<Box<Node<T>>>;
-
-pub struct LinkedList<T> {
-    head: Link<T>,
-    tail: *mut Node<T>,
+use allocator_api2::{
+    alloc::{AllocError, Allocator, Global},
+    boxed::Box,
+};
+use core::ptr;
+
+type Link<T, A> = Option<Box<Node<T, A>, A>>;
+
+pub struct LinkedList<T, A: Allocator + Clone = Global> {
+    head: Link<T, A>,
+    tail: *mut Node<T, A>,
+    alloc: A,
 }
 
-struct Node<T> {
+struct Node<T, A: Allocator + Clone> {
     elem: T,
-    next: Link<T>,
+    next: Link<T, A>,
 }
 
 impl<T> LinkedList<T> {
     pub fn new() -> Self {
+        Self::new_in(Global)
+    }
+}
+
+impl<T, A: Allocator + Clone> LinkedList<T, A> {
+    pub fn new_in(alloc: A) -> Self {
         LinkedList {
             head: None,
             tail: ptr::null_mut(),
+            alloc,
         }
     }
 
+    pub fn allocator(&self) -> &A {
+        &self.alloc
+    }
+
+    pub fn push(&mut self, elem: T) {
+        let new_node = Box::new_in(Node { elem, next: None }, self.alloc.clone());
+        self.push_node(new_node);
+    }
+
+    /// Like `push`, but `elem` is dropped and an error returned instead of
+    /// aborting if no node can be allocated.
+    pub fn try_push(&mut self, elem: T) -> Result<(), AllocError> {
+        let new_node = Box::try_new_in(Node { elem, next: None }, self.alloc.clone())?;
+        self.push_node(new_node);
+        Ok(())
+    }
+
     pub fn pop_front(&mut self) -> Option<T> {
         let node = self.head.take()?;
         if node.next.is_none() {
             self.tail = ptr::null_mut();
         }
-        self.head = node.next;
-        Some(node.elem)
+        let Node { elem, next } = Box::into_inner(node);
+        self.head = next;
+        Some(elem)
+    }
+
+    pub fn peek_front(&self) -> Option<&T> {
+        self.head.as_ref().map(|node| &node.elem)
     }
 
-    fn push_node(&mut self, mut node: Box<Node<T>>) {
+    pub fn peek_front_mut(&mut self) -> Option<&mut T> {
+        self.head.as_mut().map(|node| &mut node.elem)
+    }
+
+    fn push_node(&mut self, mut node: Box<Node<T, A>, A>) {
         if self.head.is_none() {
             self.tail = &mut *node;
             self.head = Some(node);
@@ -43,7 +81,7 @@ impl<T> LinkedList<T> {
         }
     }
 
-    pub fn concatenate(&mut self, mut list: LinkedList<T>) {
+    pub fn concatenate(&mut self, mut list: LinkedList<T, A>) {
         if self.head.is_some() {
             unsafe {
                 (*self.tail).next = list.head.take();
@@ -57,13 +95,13 @@ impl<T> LinkedList<T> {
     }
 }
 
-impl<T> Default for LinkedList<T> {
+impl<T, A: Allocator + Clone + Default> Default for LinkedList<T, A> {
     fn default() -> Self {
-        Self::new()
+        Self::new_in(A::default())
     }
 }
 
-impl<T> Drop for LinkedList<T> {
+impl<T, A: Allocator + Clone> Drop for LinkedList<T, A> {
     fn drop(&mut self) {
         let mut curr = self.head.take();
         while let Some(mut node) = curr {
@@ -72,11 +110,11 @@ impl<T> Drop for LinkedList<T> {
     }
 }
 
-pub struct Iter<'a, T> {
-    next: Option<&'a Node<T>>,
+pub struct Iter<'a, T, A: Allocator + Clone = Global> {
+    next: Option<&'a Node<T, A>>,
 }
 
-impl<'a, T> Iterator for Iter<'a, T> {
+impl<'a, T, A: Allocator + Clone> Iterator for Iter<'a, T, A> {
     type Item = &'a T;
 
     fn next(&mut self) -> Option<Self::Item> {
@@ -87,9 +125,9 @@ impl<'a, T> Iterator for Iter<'a, T> {
     }
 }
 
-impl<T> FromIterator<T> for LinkedList<T> {
+impl<T, A: Allocator + Clone + Default> FromIterator<T> for LinkedList<T, A> {
     fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
-        let mut l = Self::new();
+        let mut l = Self::default();
         for i in iter {
             l.push(i)
         }
@@ -97,10 +135,10 @@ impl<T> FromIterator<T> for LinkedList<T> {
     }
 }
 
-impl<'a, T> IntoIterator for &'a LinkedList<T> {
+impl<'a, T, A: Allocator + Clone> IntoIterator for &'a LinkedList<T, A> {
     type Item = &'a T;
 
-    type IntoIter = Iter<'a, T>;
+    type IntoIter = Iter<'a, T, A>;
 
     fn into_iter(self) -> Self::IntoIter {
         Iter {
@@ -109,18 +147,10 @@ impl<'a, T> IntoIterator for &'a LinkedList<T> {
     }
 }
 
-impl<T> List<T> for LinkedList<T> {
+impl<T, A: Allocator + Clone + Default> List<T> for LinkedList<T, A> {
+    #[inline]
     fn push(&mut self, elem: T) {
-        let mut new_node = Box::new(Node { elem, next: None });
-        let tail: *mut Node<T> = &mut *new_node;
-        if !self.tail.is_null() {
-            unsafe {
-                (*self.tail).next = Some(new_node);
-            }
-        } else {
-            self.head = Some(new_node);
-        }
-        self.tail = tail;
+        LinkedList::push(self, elem)
     }
 
     fn partition(mut self) -> Self
@@ -132,10 +162,10 @@ impl<T> List<T> for LinkedList<T> {
         }
 
         let mut first_node = self.head.take().unwrap();
-        let first = &first_node.elem;
-        let mut geq = Self::new();
         let mut curr = first_node.next.take();
-        let mut le = Self::new();
+        let first = &first_node.elem;
+        let mut geq = Self::new_in(self.alloc.clone());
+        let mut le = Self::new_in(self.alloc.clone());
 
         while let Some(mut node) = curr {
             curr = node.next.take();
@@ -156,6 +186,7 @@ impl<T> List<T> for LinkedList<T> {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::test;
 
     #[test]
     fn test() {
@@ -184,4 +215,22 @@ mod test {
     fn test_partition() {
         super::super::test::test_partition::<LinkedList<i32>>();
     }
+
+    #[test]
+    fn test_allocator() {
+        let alloc = test::CountingAllocator::default();
+        let mut l = LinkedList::new_in(&alloc);
+        for i in 0..10 {
+            l.try_push(i).unwrap();
+        }
+        assert_eq!(alloc.live(), 10);
+        assert_eq!(l.pop_front(), Some(0));
+        assert_eq!(alloc.live(), 9);
+        drop(l);
+        assert_eq!(alloc.live(), 0);
+
+        let mut l = LinkedList::new_in(test::FailingAllocator);
+        assert_eq!(l.try_push(1), Err(AllocError));
+        assert!(l.peek_front().is_none());
+    }
 }
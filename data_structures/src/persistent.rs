@@ -0,0 +1,310 @@
+use alloc::{rc::Rc, vec::Vec};
+use core::fmt::Debug;
+
+use crate::stack::Stack;
+
+type Link<T> = Option<Rc<Node<T>>>;
+
+/// An immutable singly linked list. Clones share their nodes, so keeping
+/// old versions of a list around costs nothing.
+pub struct List<T> {
+    head: Link<T>,
+    len: usize,
+}
+
+#[derive(Clone)]
+struct Node<T> {
+    elem: T,
+    next: Link<T>,
+}
+
+impl<T> List<T> {
+    pub fn new() -> Self {
+        List { head: None, len: 0 }
+    }
+
+    /// Returns a new list with `elem` in front of `self`.
+    pub fn cons(&self, elem: T) -> Self {
+        List {
+            head: Some(Rc::new(Node {
+                elem,
+                next: self.head.clone(),
+            })),
+            len: self.len + 1,
+        }
+    }
+
+    pub fn head(&self) -> Option<&T> {
+        self.head.as_ref().map(|node| &node.elem)
+    }
+
+    /// Returns the list without its first element, which is empty if `self`
+    /// is.
+    pub fn tail(&self) -> Self {
+        match &self.head {
+            Some(node) => List {
+                head: node.next.clone(),
+                len: self.len - 1,
+            },
+            None => List::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.head.is_none()
+    }
+
+    /// Whether `self` and `other` are the very same nodes.
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        match (&self.head, &other.head) {
+            (Some(a), Some(b)) => Rc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            next: self.head.as_deref(),
+        }
+    }
+}
+
+impl<T> Clone for List<T> {
+    fn clone(&self) -> Self {
+        List {
+            head: self.head.clone(),
+            len: self.len,
+        }
+    }
+}
+
+impl<T> Default for List<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for List<T> {
+    fn drop(&mut self) {
+        // Stop at the first node shared with another list.
+        let mut curr = self.head.take();
+        while let Some(node) = curr {
+            match Rc::try_unwrap(node) {
+                Ok(mut node) => curr = node.next.take(),
+                Err(_) => break,
+            }
+        }
+    }
+}
+
+pub struct Iter<'a, T> {
+    next: Option<&'a Node<T>>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next.map(|node| {
+            self.next = node.next.as_deref();
+            &node.elem
+        })
+    }
+}
+
+impl<'a, T> IntoIterator for &'a List<T> {
+    type Item = &'a T;
+
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Builds a list whose head is the first element of the iterator.
+impl<T> FromIterator<T> for List<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let elems: Vec<T> = iter.into_iter().collect();
+        elems
+            .into_iter()
+            .rev()
+            .fold(List::new(), |list, elem| list.cons(elem))
+    }
+}
+
+impl<T: PartialEq> PartialEq for List<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Debug> Debug for List<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        for i in self.iter() {
+            write!(f, "{:?} ", i)?
+        }
+        Ok(())
+    }
+}
+
+/// A stack whose clones are O(1) snapshots. Modifying a stack copies only
+/// the elements still shared with a snapshot, hence `T: Clone`.
+pub struct PersistentStack<T> {
+    list: List<T>,
+}
+
+impl<T> PersistentStack<T> {
+    /// The elements from the top to the bottom.
+    pub fn list(&self) -> &List<T> {
+        &self.list
+    }
+
+    /// Iterates from the top to the bottom.
+    pub fn iter(&self) -> Iter<'_, T> {
+        self.list.iter()
+    }
+
+    /// Iterates from the bottom to the top, like other stacks. This collects
+    /// references to all the elements first, so it allocates O(n) space.
+    pub fn iter_from_bottom(&self) -> core::iter::Rev<alloc::vec::IntoIter<&T>> {
+        self.list.iter().collect::<Vec<_>>().into_iter().rev()
+    }
+}
+
+impl<T: Clone> Stack<T> for PersistentStack<T> {
+    fn new() -> Self {
+        PersistentStack { list: List::new() }
+    }
+
+    fn push(&mut self, elem: T) {
+        self.list = self.list.cons(elem);
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        let node = self.list.head.take()?;
+        self.list.len -= 1;
+        match Rc::try_unwrap(node) {
+            Ok(node) => {
+                self.list.head = node.next;
+                Some(node.elem)
+            }
+            Err(node) => {
+                self.list.head = node.next.clone();
+                Some(node.elem.clone())
+            }
+        }
+    }
+
+    #[inline]
+    fn peek(&self) -> Option<&T> {
+        self.list.head()
+    }
+
+    fn peek_mut(&mut self) -> Option<&mut T> {
+        self.list
+            .head
+            .as_mut()
+            .map(|node| &mut Rc::make_mut(node).elem)
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    fn clear(&mut self) {
+        self.list = List::new();
+    }
+}
+
+impl<T> Clone for PersistentStack<T> {
+    fn clone(&self) -> Self {
+        PersistentStack {
+            list: self.list.clone(),
+        }
+    }
+}
+
+impl<T: Clone> Default for PersistentStack<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a PersistentStack<T> {
+    type Item = &'a T;
+
+    type IntoIter = core::iter::Rev<alloc::vec::IntoIter<&'a T>>;
+
+    // Stacks iterate from the bottom, which is the end of the list, so this
+    // allocates. `iter` does not.
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_from_bottom()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test() {
+        let empty = List::new();
+        let a = empty.cons(1);
+        let b = a.cons(2);
+        let c = a.cons(3);
+        assert!(empty.is_empty());
+        assert_eq!(b.head(), Some(&2));
+        assert_eq!(c.head(), Some(&3));
+        assert!(b.tail().ptr_eq(&c.tail()));
+        assert_eq!(b.tail().tail(), empty);
+        assert!(empty.tail().is_empty());
+        assert!(b.iter().eq([2, 1].iter()));
+        assert_eq!(c.len(), 2);
+
+        let l: List<i32> = (1..=3).collect();
+        assert!(l.iter().eq([1, 2, 3].iter()));
+        drop(a);
+        assert!(b.iter().eq([2, 1].iter()));
+    }
+
+    #[test]
+    fn test_long_drop() {
+        let mut l = List::new();
+        for i in 0..1_000_000 {
+            l = l.cons(i);
+        }
+        let snapshot = l.tail();
+        drop(l);
+        assert_eq!(snapshot.len(), 999_999);
+    }
+
+    #[test]
+    fn test_stack() {
+        crate::stack::test::test_stack::<PersistentStack<i32>>();
+
+        let mut s = PersistentStack::new();
+        for i in 1..=3 {
+            s.push(i);
+        }
+        let snapshot = s.clone();
+        *s.peek_mut().unwrap() = 10;
+        assert_eq!(s.pop(), Some(10));
+        assert_eq!(s.pop(), Some(2));
+        s.push(4);
+        assert!(s.into_iter().eq([1, 4].iter()));
+        assert!(s.iter().eq([4, 1].iter()));
+        assert!(snapshot.into_iter().eq([1, 2, 3].iter()));
+        assert_eq!(snapshot.peek(), Some(&3));
+        assert_eq!(snapshot.len(), 3);
+        s.clear();
+        assert!(s.is_empty());
+        assert!(s.pop().is_none());
+    }
+}
@@ -0,0 +1,415 @@
+use core::{
+    fmt::Debug,
+    marker::PhantomData,
+    mem,
+    ops::{Deref, DerefMut},
+};
+
+use crate::seq_list::SeqList;
+
+/// A container whose `pop` returns the element of the highest priority.
+/// Iterating over a priority queue yields its elements in arbitrary order.
+pub trait PriorityQueue<T>
+where
+    for<'a> &'a Self: IntoIterator<Item = &'a T>,
+{
+    fn new() -> Self;
+
+    fn with_capacity(_capacity: usize) -> Self
+    where
+        Self: Sized,
+    {
+        Self::new()
+    }
+
+    fn push(&mut self, elem: T);
+    fn pop(&mut self) -> Option<T>;
+    fn peek(&self) -> Option<&T>;
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn clear(&mut self) {
+        while self.pop().is_some() {}
+    }
+}
+
+/// Decides which of two elements a heap should pop first.
+pub trait HeapOrder {
+    fn precedes<T: Ord>(a: &T, b: &T) -> bool;
+}
+
+/// The greatest element has the highest priority.
+pub struct Max;
+
+/// The least element has the highest priority.
+pub struct Min;
+
+impl HeapOrder for Max {
+    #[inline]
+    fn precedes<T: Ord>(a: &T, b: &T) -> bool {
+        a > b
+    }
+}
+
+impl HeapOrder for Min {
+    #[inline]
+    fn precedes<T: Ord>(a: &T, b: &T) -> bool {
+        a < b
+    }
+}
+
+pub struct BinaryHeap<T, O = Max> {
+    list: SeqList<T>,
+    marker: PhantomData<O>,
+}
+
+pub type MinHeap<T> = BinaryHeap<T, Min>;
+
+impl<T: Ord, O: HeapOrder> BinaryHeap<T, O> {
+    pub fn new() -> Self {
+        BinaryHeap {
+            list: SeqList::new(),
+            marker: PhantomData,
+        }
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        BinaryHeap {
+            list: SeqList::with_capacity(capacity),
+            marker: PhantomData,
+        }
+    }
+
+    pub fn push(&mut self, elem: T) {
+        self.list.push(elem);
+        self.sift_up(self.list.len() - 1);
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        let len = self.list.len();
+        if len > 1 {
+            self.list.swap(0, len - 1);
+        }
+        let elem = self.list.pop()?;
+        self.sift_down(0, self.list.len());
+        Some(elem)
+    }
+
+    pub fn peek(&self) -> Option<&T> {
+        self.list.first()
+    }
+
+    /// The heap is restored when the returned guard is dropped, so the top
+    /// element may be changed to any value.
+    pub fn peek_mut(&mut self) -> Option<PeekMut<'_, T, O>> {
+        if self.list.is_empty() {
+            None
+        } else {
+            Some(PeekMut { heap: self })
+        }
+    }
+
+    /// Moves all the elements of `other` into `self`, leaving `other` empty.
+    pub fn append(&mut self, other: &mut Self) {
+        if other.list.is_empty() {
+            return;
+        }
+        if self.list.len() < other.list.len() {
+            mem::swap(self, other);
+        }
+        self.list.concatenate(mem::take(&mut other.list));
+        self.rebuild();
+    }
+
+    /// Returns the elements in ascending order of priority, so the element
+    /// `pop` would have returned first comes last.
+    pub fn into_sorted_list(mut self) -> SeqList<T> {
+        let mut end = self.list.len();
+        while end > 1 {
+            end -= 1;
+            self.list.swap(0, end);
+            self.sift_down(0, end);
+        }
+        self.into_list()
+    }
+
+    /// Returns the underlying list in heap order.
+    pub fn into_list(self) -> SeqList<T> {
+        self.list
+    }
+
+    pub fn iter(&self) -> core::slice::Iter<'_, T> {
+        self.list.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.list = SeqList::new();
+    }
+
+    fn sift_up(&mut self, mut pos: usize) {
+        while pos > 0 {
+            let parent = (pos - 1) / 2;
+            if !O::precedes(&self.list[pos], &self.list[parent]) {
+                break;
+            }
+            self.list.swap(pos, parent);
+            pos = parent;
+        }
+    }
+
+    /// Only the first `end` elements are treated as part of the heap.
+    fn sift_down(&mut self, mut pos: usize, end: usize) {
+        loop {
+            let left = 2 * pos + 1;
+            if left >= end {
+                break;
+            }
+            let right = left + 1;
+            let child = if right < end && O::precedes(&self.list[right], &self.list[left]) {
+                right
+            } else {
+                left
+            };
+            if !O::precedes(&self.list[child], &self.list[pos]) {
+                break;
+            }
+            self.list.swap(pos, child);
+            pos = child;
+        }
+    }
+
+    /// Heapifies the whole list bottom-up in O(n).
+    fn rebuild(&mut self) {
+        let len = self.list.len();
+        for pos in (0..len / 2).rev() {
+            self.sift_down(pos, len);
+        }
+    }
+}
+
+impl<T: Ord, O: HeapOrder> PriorityQueue<T> for BinaryHeap<T, O> {
+    fn new() -> Self {
+        BinaryHeap::new()
+    }
+
+    fn with_capacity(capacity: usize) -> Self {
+        BinaryHeap::with_capacity(capacity)
+    }
+
+    #[inline]
+    fn push(&mut self, elem: T) {
+        BinaryHeap::push(self, elem)
+    }
+
+    #[inline]
+    fn pop(&mut self) -> Option<T> {
+        BinaryHeap::pop(self)
+    }
+
+    #[inline]
+    fn peek(&self) -> Option<&T> {
+        BinaryHeap::peek(self)
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        BinaryHeap::len(self)
+    }
+
+    fn clear(&mut self) {
+        BinaryHeap::clear(self)
+    }
+}
+
+pub struct PeekMut<'a, T: Ord, O: HeapOrder> {
+    heap: &'a mut BinaryHeap<T, O>,
+}
+
+impl<'a, T: Ord, O: HeapOrder> PeekMut<'a, T, O> {
+    pub fn pop(this: Self) -> T {
+        this.heap.pop().unwrap()
+    }
+}
+
+impl<'a, T: Ord, O: HeapOrder> Deref for PeekMut<'a, T, O> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.heap.list[0]
+    }
+}
+
+impl<'a, T: Ord, O: HeapOrder> DerefMut for PeekMut<'a, T, O> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.heap.list[0]
+    }
+}
+
+impl<'a, T: Ord, O: HeapOrder> Drop for PeekMut<'a, T, O> {
+    fn drop(&mut self) {
+        let len = self.heap.list.len();
+        self.heap.sift_down(0, len);
+    }
+}
+
+impl<T: Ord, O: HeapOrder> Default for BinaryHeap<T, O> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Ord, O: HeapOrder> From<SeqList<T>> for BinaryHeap<T, O> {
+    fn from(list: SeqList<T>) -> Self {
+        let mut heap = BinaryHeap {
+            list,
+            marker: PhantomData,
+        };
+        heap.rebuild();
+        heap
+    }
+}
+
+impl<T: Ord, O: HeapOrder> FromIterator<T> for BinaryHeap<T, O> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        iter.into_iter().collect::<SeqList<T>>().into()
+    }
+}
+
+impl<'a, T: Ord, O: HeapOrder> IntoIterator for &'a BinaryHeap<T, O> {
+    type Item = &'a T;
+
+    type IntoIter = core::slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T: Debug, O> Debug for BinaryHeap<T, O> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.list.fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::Rng;
+
+    fn random_vec(len: usize) -> Vec<i32> {
+        let mut rng = rand::thread_rng();
+        (0..len).map(|_| rng.gen_range(-100..100)).collect()
+    }
+
+    fn test_priority_queue<Q: PriorityQueue<i32>>(descending: bool)
+    where
+        for<'a> &'a Q: IntoIterator<Item = &'a i32>,
+    {
+        for len in [0, 1, 2, 3, 10, 100, 1000] {
+            let v = random_vec(len);
+            let mut q = Q::with_capacity(len);
+            for &i in v.iter() {
+                q.push(i);
+            }
+            assert_eq!(q.len(), len);
+            assert_eq!(q.into_iter().count(), len);
+
+            let mut expect = v.clone();
+            expect.sort_unstable();
+            if descending {
+                expect.reverse();
+            }
+            let mut got = Vec::new();
+            while let Some(&top) = q.peek() {
+                assert_eq!(q.pop(), Some(top));
+                got.push(top);
+            }
+            assert_eq!(got, expect);
+            assert!(q.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_max_heap() {
+        test_priority_queue::<BinaryHeap<i32>>(true);
+    }
+
+    #[test]
+    fn test_min_heap() {
+        test_priority_queue::<MinHeap<i32>>(false);
+    }
+
+    #[test]
+    fn test_from_iter() {
+        let v = random_vec(1000);
+        let mut heap: BinaryHeap<i32> = v.iter().copied().collect();
+        let mut expect = v;
+        expect.sort_unstable();
+        while let Some(i) = heap.pop() {
+            assert_eq!(i, expect.pop().unwrap());
+        }
+        assert!(expect.is_empty());
+    }
+
+    #[test]
+    fn test_peek_mut() {
+        let mut heap: BinaryHeap<i32> = vec![5, 3, 8, 1].into_iter().collect();
+        *heap.peek_mut().unwrap() = 0;
+        assert_eq!(heap.peek(), Some(&5));
+        {
+            let mut top = heap.peek_mut().unwrap();
+            *top = 10;
+        }
+        assert_eq!(heap.peek(), Some(&10));
+        assert_eq!(PeekMut::pop(heap.peek_mut().unwrap()), 10);
+        assert_eq!(heap.into_sorted_list()[..], [0, 1, 3]);
+
+        let mut heap = MinHeap::<i32>::new();
+        assert!(heap.peek_mut().is_none());
+        heap.push(1);
+        heap.push(2);
+        *heap.peek_mut().unwrap() = 3;
+        assert_eq!(heap.pop(), Some(2));
+        assert_eq!(heap.pop(), Some(3));
+    }
+
+    #[test]
+    fn test_into_sorted_list() {
+        let v = random_vec(1000);
+        let mut expect = v.clone();
+        expect.sort_unstable();
+
+        let heap: BinaryHeap<i32> = v.iter().copied().collect();
+        assert_eq!(heap.into_sorted_list()[..], expect[..]);
+
+        let heap: MinHeap<i32> = v.iter().copied().collect();
+        expect.reverse();
+        assert_eq!(heap.into_sorted_list()[..], expect[..]);
+    }
+
+    #[test]
+    fn test_append() {
+        let a = random_vec(100);
+        let b = random_vec(10);
+        let mut expect: Vec<i32> = a.iter().chain(b.iter()).copied().collect();
+        expect.sort_unstable();
+
+        let mut heap_a: BinaryHeap<i32> = a.iter().copied().collect();
+        let mut heap_b: BinaryHeap<i32> = b.iter().copied().collect();
+        heap_b.append(&mut heap_a);
+        assert!(heap_a.is_empty());
+        heap_b.append(&mut heap_a);
+        assert_eq!(heap_b.into_sorted_list()[..], expect[..]);
+    }
+}
@@ -1,50 +1,164 @@
-use std::{
-    alloc::{self, realloc, Layout},
-    marker::PhantomData,
-    ptr::{self, NonNull},
-};
+#[cfg(feature = "alloc")]
+use allocator_api2::alloc::{handle_alloc_error, AllocError, Allocator, Global, Layout};
+#[cfg(feature = "alloc")]
+use core::ptr;
+use core::{marker::PhantomData, mem::MaybeUninit, ptr::NonNull};
 
-use crate::{linked_list, List};
+#[cfg(feature = "alloc")]
+use crate::linked_list;
 
-pub trait Queue<T> {
+/// A FIFO container. Iterating over a queue yields its elements from the
+/// front to the back.
+pub trait Queue<T>
+where
+    for<'a> &'a Self: IntoIterator<Item = &'a T>,
+{
     fn new() -> Self;
+
+    fn with_capacity(_capacity: usize) -> Self
+    where
+        Self: Sized,
+    {
+        Self::new()
+    }
+
     fn push(&mut self, elem: T);
     fn pop_front(&mut self) -> Option<T>;
+    fn peek(&self) -> Option<&T>;
+    fn peek_mut(&mut self) -> Option<&mut T>;
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn clear(&mut self) {
+        while self.pop_front().is_some() {}
+    }
 }
 
-pub struct SeqQueue<T> {
+#[cfg(feature = "alloc")]
+pub struct SeqQueue<T, A: Allocator = Global> {
     ptr: NonNull<T>,
     capacity: usize,
     marker: PhantomData<T>,
     head: usize,
     tail: usize,
+    alloc: A,
 }
 
-impl<T> Queue<T> for SeqQueue<T> {
+#[cfg(feature = "alloc")]
+impl<T, A: Allocator + Default> Queue<T> for SeqQueue<T, A> {
     fn new() -> Self {
+        Self::new_in(A::default())
+    }
+
+    fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_in(capacity, A::default())
+    }
+
+    #[inline]
+    fn push(&mut self, elem: T) {
+        SeqQueue::push(self, elem)
+    }
+
+    #[inline]
+    fn pop_front(&mut self) -> Option<T> {
+        SeqQueue::pop_front(self)
+    }
+
+    #[inline]
+    fn peek(&self) -> Option<&T> {
+        SeqQueue::peek(self)
+    }
+
+    #[inline]
+    fn peek_mut(&mut self) -> Option<&mut T> {
+        SeqQueue::peek_mut(self)
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        SeqQueue::len(self)
+    }
+
+    #[inline]
+    fn is_empty(&self) -> bool {
+        SeqQueue::is_empty(self)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T> SeqQueue<T> {
+    pub fn new() -> Self {
+        Self::new_in(Global)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_in(capacity, Global)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T, A: Allocator> SeqQueue<T, A> {
+    // A ring of one slot holds no elements, so it is left unallocated.
+    pub fn new_in(alloc: A) -> Self {
         SeqQueue {
-            ptr: unsafe {
-                NonNull::<T>::new(alloc::alloc(Layout::new::<T>()) as *mut T)
-                    .unwrap_or_else(|| alloc::handle_alloc_error(Layout::new::<T>()))
-            },
+            ptr: NonNull::dangling(),
             capacity: 1,
             marker: PhantomData,
             head: 0,
             tail: 0,
+            alloc,
         }
     }
-    fn push(&mut self, elem: T) {
+
+    pub fn with_capacity_in(capacity: usize, alloc: A) -> Self {
+        let mut q = Self::new_in(alloc);
+        // One slot is always left empty to tell a full queue from an empty one.
+        let capacity = (capacity + 1).next_power_of_two();
+        if capacity > 1 {
+            q.grow_to(capacity);
+        }
+        q
+    }
+
+    pub fn push(&mut self, elem: T) {
         if self.is_full() {
-            let old_capacity = self.capacity;
-            self.grow();
-            self.reorganise(old_capacity);
+            self.grow_to(self.capacity * 2);
         }
         unsafe {
             self.ptr.as_ptr().add(self.tail).write(elem);
         }
         self.tail = get_real_index(self.tail + 1, self.capacity);
     }
-    fn pop_front(&mut self) -> Option<T> {
+
+    /// Like `push`, but `elem` is dropped and an error returned instead of
+    /// aborting if the queue cannot grow.
+    pub fn try_push(&mut self, elem: T) -> Result<(), AllocError> {
+        self.try_reserve(1)?;
+        unsafe {
+            self.ptr.as_ptr().add(self.tail).write(elem);
+        }
+        self.tail = get_real_index(self.tail + 1, self.capacity);
+        Ok(())
+    }
+
+    /// Makes room for at least `additional` more elements.
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), AllocError> {
+        let capacity = self
+            .len()
+            .checked_add(additional)
+            .and_then(|needed| needed.checked_add(1))
+            .and_then(usize::checked_next_power_of_two)
+            .ok_or(AllocError)?;
+        if capacity > self.capacity {
+            self.try_grow_to(capacity)?;
+        }
+        Ok(())
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
         if self.is_empty() {
             None
         } else {
@@ -53,45 +167,78 @@ impl<T> Queue<T> for SeqQueue<T> {
             Some(elem)
         }
     }
-}
 
-impl<T> SeqQueue<T> {
-    fn grow(&mut self) {
-        let new_capacity: usize;
-        let new_ptr: *mut u8;
-        let new_layout: Layout;
-
-        if self.capacity == 0 {
-            new_capacity = 1;
-            new_layout = Layout::array::<T>(1).unwrap();
-            new_ptr = unsafe { alloc::alloc(new_layout) };
+    pub fn peek(&self) -> Option<&T> {
+        if self.is_empty() {
+            None
         } else {
-            new_capacity = self.capacity * 2;
-            new_layout = Layout::array::<T>(new_capacity).unwrap();
-            assert!(new_capacity <= isize::MAX as usize, "Allocation too large");
-            let old_layout = Layout::array::<T>(self.capacity).unwrap();
-            new_ptr =
-                unsafe { realloc(self.ptr.as_ptr() as *mut u8, old_layout, new_layout.size()) }
-        };
+            unsafe { self.ptr.as_ptr().add(self.head).as_ref() }
+        }
+    }
 
-        self.ptr = NonNull::new(new_ptr as *mut T)
-            .unwrap_or_else(|| alloc::handle_alloc_error(new_layout));
-        self.capacity = new_capacity;
+    pub fn peek_mut(&mut self) -> Option<&mut T> {
+        if self.is_empty() {
+            None
+        } else {
+            unsafe { self.ptr.as_ptr().add(self.head).as_mut() }
+        }
     }
 
-    fn len(&self) -> usize {
+    pub fn len(&self) -> usize {
         self.tail.wrapping_sub(self.head) & (self.capacity.wrapping_sub(1))
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.head == self.tail
+    }
+
+    pub fn clear(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+
+    pub fn allocator(&self) -> &A {
+        &self.alloc
+    }
+
+    /// Empties the queue without dropping the elements.
+    ///
+    /// # Safety
+    /// The elements should have been moved out already.
+    #[cfg(feature = "rayon")]
+    pub(crate) unsafe fn forget_elements(&mut self) {
+        self.tail = self.head;
+    }
+
+    fn grow_to(&mut self, new_capacity: usize) {
+        let new_layout = Layout::array::<T>(new_capacity).expect("Allocation too large");
+        if self.try_grow_to(new_capacity).is_err() {
+            handle_alloc_error(new_layout)
+        }
+    }
+
+    /// `new_capacity` must be a power of two greater than the current one.
+    fn try_grow_to(&mut self, new_capacity: usize) -> Result<(), AllocError> {
+        let new_layout = Layout::array::<T>(new_capacity).map_err(|_| AllocError)?;
+
+        let new_ptr = if self.capacity == 1 {
+            self.alloc.allocate(new_layout)?
+        } else {
+            let old_layout = Layout::array::<T>(self.capacity).unwrap();
+            unsafe { self.alloc.grow(self.ptr.cast(), old_layout, new_layout)? }
+        };
+
+        let old_capacity = self.capacity;
+        self.ptr = new_ptr.cast();
+        self.capacity = new_capacity;
+        self.reorganise(old_capacity);
+        Ok(())
+    }
+
     #[allow(dead_code)]
     fn capacity(&self) -> usize {
         self.capacity
     }
 
-    fn is_empty(&self) -> bool {
-        self.head == self.tail
-    }
-
     fn is_full(&mut self) -> bool {
         self.len() + 1 >= self.capacity
     }
@@ -104,7 +251,7 @@ impl<T> SeqQueue<T> {
             unsafe {
                 let ptr = self.ptr.as_ptr();
                 let src = ptr.add(self.head);
-                let new_head = self.head + old_capacity;
+                let new_head = self.head + self.capacity - old_capacity;
                 let dst = ptr.add(new_head);
                 let count = old_capacity - self.head;
                 ptr::copy_nonoverlapping(src, dst, count);
@@ -113,52 +260,481 @@ impl<T> SeqQueue<T> {
         }
     }
 
-    pub fn peek_front_mut(&mut self) -> Option<&mut T> {
+    /// Returns the `index`th element counting from the front.
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index < self.len() {
+            let index = get_real_index(self.head + index, self.capacity);
+            unsafe { self.ptr.as_ptr().add(index).as_ref() }
+        } else {
+            None
+        }
+    }
+
+    pub fn peek_back(&self) -> Option<&T> {
         if self.is_empty() {
             None
         } else {
-            unsafe { self.ptr.as_ptr().add(self.head).as_mut() }
+            let index = get_real_index(self.tail.wrapping_sub(1), self.capacity);
+            unsafe { self.ptr.as_ptr().add(index).as_ref() }
+        }
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        if self.is_empty() {
+            None
+        } else {
+            self.tail = get_real_index(self.tail.wrapping_sub(1), self.capacity);
+            Some(unsafe { self.ptr.as_ptr().add(self.tail).read() })
+        }
+    }
+
+    /// The elements from the front to the back, in the two parts of the ring
+    /// they occupy. The second slice is empty unless the queue wraps around.
+    pub fn as_slices(&self) -> (&[T], &[T]) {
+        let ptr = self.ptr.as_ptr();
+        unsafe {
+            if self.head <= self.tail {
+                (
+                    core::slice::from_raw_parts(ptr.add(self.head), self.tail - self.head),
+                    &[],
+                )
+            } else {
+                (
+                    core::slice::from_raw_parts(ptr.add(self.head), self.capacity - self.head),
+                    core::slice::from_raw_parts(ptr, self.tail),
+                )
+            }
+        }
+    }
+
+    pub fn as_mut_slices(&mut self) -> (&mut [T], &mut [T]) {
+        let ptr = self.ptr.as_ptr();
+        unsafe {
+            if self.head <= self.tail {
+                (
+                    core::slice::from_raw_parts_mut(ptr.add(self.head), self.tail - self.head),
+                    &mut [],
+                )
+            } else {
+                (
+                    core::slice::from_raw_parts_mut(ptr.add(self.head), self.capacity - self.head),
+                    core::slice::from_raw_parts_mut(ptr, self.tail),
+                )
+            }
+        }
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            ptr: self.ptr,
+            capacity: self.capacity,
+            index: self.head,
+            len: self.len(),
+            marker: PhantomData,
+        }
+    }
+}
+
+pub struct Iter<'a, T> {
+    ptr: NonNull<T>,
+    capacity: usize,
+    index: usize,
+    len: usize,
+    marker: PhantomData<&'a T>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            None
+        } else {
+            let elem = unsafe { &*self.ptr.as_ptr().add(self.index) };
+            self.index = get_real_index(self.index + 1, self.capacity);
+            self.len -= 1;
+            Some(elem)
         }
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, T> ExactSizeIterator for Iter<'a, T> {}
+
+#[cfg(feature = "nightly")]
+unsafe impl<'a, T> core::iter::TrustedLen for Iter<'a, T> {}
+
+#[cfg(feature = "alloc")]
+impl<'a, T, A: Allocator> IntoIterator for &'a SeqQueue<T, A> {
+    type Item = &'a T;
+
+    type IntoIter = Iter<'a, T>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T, A: Allocator + Default> Default for SeqQueue<T, A> {
+    fn default() -> Self {
+        Self::new_in(A::default())
+    }
 }
 
 #[inline]
-fn get_real_index(index: usize, capacity: usize) -> usize {
+pub(crate) fn get_real_index(index: usize, capacity: usize) -> usize {
     index & (capacity - 1) // take the lower bits = index % self.capacity
 }
 
-impl<T> Drop for SeqQueue<T> {
+#[cfg(feature = "alloc")]
+impl<T, A: Allocator> Drop for SeqQueue<T, A> {
     fn drop(&mut self) {
         while self.pop_front().is_some() {}
+        if self.capacity == 1 {
+            return;
+        }
         let layout = Layout::array::<T>(self.capacity).unwrap();
-        unsafe { alloc::dealloc(self.ptr.as_ptr() as *mut u8, layout) }
+        unsafe { self.alloc.deallocate(self.ptr.cast(), layout) }
     }
 }
 
-struct LinkedQueue<T> {
+#[cfg(feature = "alloc")]
+pub struct LinkedQueue<T> {
     list: linked_list::LinkedList<T>,
+    len: usize,
 }
 
+#[cfg(feature = "alloc")]
 impl<T> Queue<T> for LinkedQueue<T> {
     fn new() -> Self {
         LinkedQueue {
             list: linked_list::LinkedList::new(),
+            len: 0,
         }
     }
 
     fn push(&mut self, elem: T) {
-        self.list.push(elem)
+        self.list.push(elem);
+        self.len += 1;
     }
 
     fn pop_front(&mut self) -> Option<T> {
-        self.list.pop_front()
+        let elem = self.list.pop_front()?;
+        self.len -= 1;
+        Some(elem)
+    }
+
+    fn peek(&self) -> Option<&T> {
+        self.list.peek_front()
+    }
+
+    fn peek_mut(&mut self) -> Option<&mut T> {
+        self.list.peek_front_mut()
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn clear(&mut self) {
+        self.list = linked_list::LinkedList::new();
+        self.len = 0;
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T> Default for LinkedQueue<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'a, T> IntoIterator for &'a LinkedQueue<T> {
+    type Item = &'a T;
+
+    type IntoIter = linked_list::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        (&self.list).into_iter()
+    }
+}
+
+/// A queue that also answers `max` and `min` in amortised O(1), e.g. for
+/// the maximum of a sliding window.
+#[cfg(feature = "alloc")]
+pub struct MonotonicQueue<T> {
+    items: SeqQueue<T>,
+    // Sequence numbers of the candidates for the maximum and the minimum,
+    // counting every element ever pushed.
+    maxima: SeqQueue<usize>,
+    minima: SeqQueue<usize>,
+    front_seq: usize,
+    // Set once the front element has been handed out by `peek_mut`; it is
+    // then left out of `maxima` and `minima` and compared on its own.
+    front_detached: bool,
+}
+
+#[cfg(feature = "alloc")]
+impl<T: Ord> MonotonicQueue<T> {
+    pub fn max(&self) -> Option<&T> {
+        self.extreme(&self.maxima, |a, b| a > b)
+    }
+
+    pub fn min(&self) -> Option<&T> {
+        self.extreme(&self.minima, |a, b| a < b)
+    }
+
+    fn extreme(&self, candidates: &SeqQueue<usize>, precedes: fn(&T, &T) -> bool) -> Option<&T> {
+        let best = candidates.peek().map(|&seq| self.get_seq(seq));
+        match (best, self.front_detached) {
+            (Some(best), true) => {
+                let front = self.items.peek().unwrap();
+                Some(if precedes(front, best) { front } else { best })
+            }
+            (None, true) => self.items.peek(),
+            (best, false) => best,
+        }
+    }
+
+    fn get_seq(&self, seq: usize) -> &T {
+        self.items.get(seq - self.front_seq).unwrap()
+    }
+
+    fn push_candidate(&mut self, is_max: bool, elem: &T) {
+        let seq = self.front_seq + self.items.len();
+        let candidates = if is_max {
+            &mut self.maxima
+        } else {
+            &mut self.minima
+        };
+        while let Some(&back) = candidates.peek_back() {
+            let back = self.items.get(back - self.front_seq).unwrap();
+            if (is_max && back > elem) || (!is_max && back < elem) {
+                break;
+            }
+            candidates.pop_back();
+        }
+        candidates.push(seq);
+    }
+
+    fn detach_front(&mut self) {
+        for candidates in [&mut self.maxima, &mut self.minima] {
+            if candidates.peek() == Some(&self.front_seq) {
+                candidates.pop_front();
+            }
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: Ord> Queue<T> for MonotonicQueue<T> {
+    fn new() -> Self {
+        MonotonicQueue {
+            items: SeqQueue::new(),
+            maxima: SeqQueue::new(),
+            minima: SeqQueue::new(),
+            front_seq: 0,
+            front_detached: false,
+        }
+    }
+
+    fn with_capacity(capacity: usize) -> Self {
+        MonotonicQueue {
+            items: SeqQueue::with_capacity(capacity),
+            maxima: SeqQueue::with_capacity(capacity),
+            minima: SeqQueue::with_capacity(capacity),
+            front_seq: 0,
+            front_detached: false,
+        }
+    }
+
+    fn push(&mut self, elem: T) {
+        self.push_candidate(true, &elem);
+        self.push_candidate(false, &elem);
+        self.items.push(elem);
+    }
+
+    fn pop_front(&mut self) -> Option<T> {
+        let elem = self.items.pop_front()?;
+        if self.front_detached {
+            self.front_detached = false;
+        } else {
+            self.detach_front();
+        }
+        self.front_seq += 1;
+        Some(elem)
+    }
+
+    #[inline]
+    fn peek(&self) -> Option<&T> {
+        self.items.peek()
+    }
+
+    fn peek_mut(&mut self) -> Option<&mut T> {
+        if !self.items.is_empty() && !self.front_detached {
+            self.detach_front();
+            self.front_detached = true;
+        }
+        self.items.peek_mut()
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    fn clear(&mut self) {
+        self.items.clear();
+        self.maxima.clear();
+        self.minima.clear();
+        self.front_seq = 0;
+        self.front_detached = false;
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: Ord> Default for MonotonicQueue<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'a, T> IntoIterator for &'a MonotonicQueue<T> {
+    type Item = &'a T;
+
+    type IntoIter = Iter<'a, T>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.iter()
+    }
+}
+
+/// A ring buffer of at most `N` elements that never allocates. `N` has to
+/// be a power of two.
+pub struct ArrayQueue<T, const N: usize> {
+    buf: [MaybeUninit<T>; N],
+    head: usize,
+    len: usize,
+}
+
+impl<T, const N: usize> ArrayQueue<T, N> {
+    /// Gives `elem` back if the queue is full.
+    pub fn try_push(&mut self, elem: T) -> Result<(), T> {
+        if self.is_full() {
+            return Err(elem);
+        }
+        self.buf[get_real_index(self.head + self.len, N)].write(elem);
+        self.len += 1;
+        Ok(())
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len == N
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            ptr: NonNull::from(&self.buf).cast(),
+            capacity: N,
+            index: self.head,
+            len: self.len,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<T, const N: usize> Queue<T> for ArrayQueue<T, N> {
+    fn new() -> Self {
+        const { assert!(N.is_power_of_two(), "The capacity should be a power of two") };
+        ArrayQueue {
+            buf: [const { MaybeUninit::uninit() }; N],
+            head: 0,
+            len: 0,
+        }
+    }
+
+    /// # Panics
+    /// Panics if the queue is full.
+    fn push(&mut self, elem: T) {
+        if self.try_push(elem).is_err() {
+            panic!("ArrayQueue is full");
+        }
+    }
+
+    fn pop_front(&mut self) -> Option<T> {
+        if self.len == 0 {
+            None
+        } else {
+            let elem = unsafe { self.buf[self.head].assume_init_read() };
+            self.head = get_real_index(self.head + 1, N);
+            self.len -= 1;
+            Some(elem)
+        }
+    }
+
+    fn peek(&self) -> Option<&T> {
+        if self.len == 0 {
+            None
+        } else {
+            Some(unsafe { self.buf[self.head].assume_init_ref() })
+        }
+    }
+
+    fn peek_mut(&mut self) -> Option<&mut T> {
+        if self.len == 0 {
+            None
+        } else {
+            Some(unsafe { self.buf[self.head].assume_init_mut() })
+        }
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+impl<T, const N: usize> Default for ArrayQueue<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> Drop for ArrayQueue<T, N> {
+    fn drop(&mut self) {
+        self.clear()
+    }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a ArrayQueue<T, N> {
+    type Item = &'a T;
+
+    type IntoIter = Iter<'a, T>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
     }
 }
 
 #[cfg(test)]
 mod test {
+    #[cfg(feature = "alloc")]
+    use std::collections::VecDeque;
+
     use super::*;
+    #[cfg(feature = "alloc")]
+    use rand::Rng;
 
+    #[cfg(feature = "alloc")]
     #[test]
     fn test_seq_queue() {
         let mut q = SeqQueue::<i32>::new();
@@ -180,6 +756,7 @@ mod test {
         assert_eq!(q.pop_front().unwrap(), 2);
     }
 
+    #[cfg(feature = "alloc")]
     #[test]
     fn test_seq_queue_drop() {
         let mut q = SeqQueue::new();
@@ -188,6 +765,7 @@ mod test {
         }
     }
 
+    #[cfg(feature = "alloc")]
     #[test]
     fn test_reorganise() {
         let mut q: SeqQueue<i32> = SeqQueue::new();
@@ -210,6 +788,152 @@ mod test {
         q.pop_front();
         q.push(100);
         q.pop_front();
-        assert_eq!(q.peek_front_mut().unwrap(), &100);
+        assert_eq!(q.peek_mut().unwrap(), &100);
+    }
+
+    fn test_queue<Q: Queue<i32>>()
+    where
+        for<'a> &'a Q: IntoIterator<Item = &'a i32>,
+    {
+        let mut q = Q::with_capacity(4);
+        assert!(q.is_empty());
+        assert!(q.peek().is_none());
+        for i in 1..=10 {
+            q.push(i);
+        }
+        assert_eq!(q.len(), 10);
+        assert_eq!(q.peek(), Some(&1));
+        assert!(q.into_iter().eq((1..=10).collect::<Vec<_>>().iter()));
+
+        *q.peek_mut().unwrap() = 100;
+        assert_eq!(q.pop_front(), Some(100));
+        for i in 11..=20 {
+            q.push(i);
+            q.pop_front();
+        }
+        assert!(q.into_iter().eq((12..=20).collect::<Vec<_>>().iter()));
+        assert_eq!(q.len(), 9);
+
+        q.clear();
+        assert!(q.is_empty());
+        assert!(q.pop_front().is_none());
+        q.push(1);
+        assert_eq!(q.pop_front(), Some(1));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_queue_trait() {
+        test_queue::<SeqQueue<i32>>();
+        test_queue::<LinkedQueue<i32>>();
+    }
+
+    #[test]
+    fn test_array_queue() {
+        test_queue::<ArrayQueue<i32, 16>>();
+
+        let mut q = ArrayQueue::<i32, 4>::new();
+        for i in 0..10 {
+            if q.is_full() {
+                q.pop_front();
+            }
+            assert_eq!(q.try_push(i), Ok(()));
+        }
+        // The ring has wrapped around by now.
+        assert!(q.is_full());
+        assert_eq!(q.try_push(10), Err(10));
+        assert!(q.iter().eq([6, 7, 8, 9].iter()));
+        assert_eq!(q.pop_front(), Some(6));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_seq_queue_back() {
+        let mut q = SeqQueue::new();
+        assert!(q.pop_back().is_none());
+        for i in 0..10 {
+            q.push(i);
+            q.pop_front();
+            q.push(i);
+        }
+        assert_eq!(q.peek_back(), Some(&9));
+        assert_eq!(q.get(0), Some(&5));
+        assert_eq!(q.get(9), Some(&9));
+        assert_eq!(q.get(10), None);
+        assert_eq!(q.pop_back(), Some(9));
+        assert_eq!(q.pop_back(), Some(9));
+        assert_eq!(q.pop_back(), Some(8));
+        assert_eq!(q.len(), 7);
+        assert!(q.iter().eq([5, 5, 6, 6, 7, 7, 8].iter()));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_monotonic_queue() {
+        test_queue::<MonotonicQueue<i32>>();
+
+        let mut rng = rand::thread_rng();
+        let mut q = MonotonicQueue::new();
+        let mut model = VecDeque::new();
+        assert!(q.max().is_none());
+        for _ in 0..1000 {
+            match rng.gen_range(0..4) {
+                0 | 1 => {
+                    let i: i32 = rng.gen_range(-100..100);
+                    q.push(i);
+                    model.push_back(i);
+                }
+                2 => assert_eq!(q.pop_front(), model.pop_front()),
+                _ => {
+                    let i: i32 = rng.gen_range(-100..100);
+                    if let Some(front) = q.peek_mut() {
+                        *front = i;
+                        *model.front_mut().unwrap() = i;
+                    }
+                }
+            }
+            assert_eq!(q.max(), model.iter().max());
+            assert_eq!(q.min(), model.iter().min());
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_sliding_window_max() {
+        let v = [1, 3, -1, -3, 5, 3, 6, 7];
+        let mut q = MonotonicQueue::with_capacity(3);
+        let mut maxima = Vec::new();
+        for i in v {
+            q.push(i);
+            if q.len() > 3 {
+                q.pop_front();
+            }
+            if q.len() == 3 {
+                maxima.push(*q.max().unwrap());
+            }
+        }
+        assert_eq!(maxima, [3, 3, 5, 5, 6, 7]);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_allocator() {
+        let alloc = super::super::test::CountingAllocator::default();
+        let mut q = SeqQueue::new_in(&alloc);
+        for i in 0..100 {
+            q.push(i);
+            if i % 3 == 0 {
+                q.pop_front();
+            }
+        }
+        assert!(q.iter().eq((34..100).collect::<Vec<_>>().iter()));
+        assert_eq!(alloc.live(), 1);
+        drop(q);
+        assert_eq!(alloc.live(), 0);
+
+        let mut q = SeqQueue::new_in(super::super::test::FailingAllocator);
+        assert_eq!(q.try_push(1), Err(AllocError));
+        assert!(q.is_empty());
+        assert!(q.try_reserve(usize::MAX).is_err());
     }
 }
@@ -0,0 +1,188 @@
+//! Parallel iterators over `SeqList` and `SeqQueue`. A list is split like
+//! the slice it derefs to and a queue like the two halves of its ring, so
+//! `par_iter` and `par_iter_mut` come from `IntoParallelIterator` for
+//! references.
+
+use alloc::vec::Vec;
+use core::ptr;
+
+use allocator_api2::alloc::Allocator;
+use rayon::{
+    iter::{Chain, IntoParallelIterator, ParallelExtend, ParallelIterator},
+    slice::{self, ParallelSliceMut},
+    vec,
+};
+
+use crate::{queue::SeqQueue, seq_list::SeqList};
+
+impl<'a, T: Sync, A: Allocator> IntoParallelIterator for &'a SeqList<T, A> {
+    type Iter = slice::Iter<'a, T>;
+    type Item = &'a T;
+
+    fn into_par_iter(self) -> Self::Iter {
+        (**self).into_par_iter()
+    }
+}
+
+impl<'a, T: Send, A: Allocator> IntoParallelIterator for &'a mut SeqList<T, A> {
+    type Iter = slice::IterMut<'a, T>;
+    type Item = &'a mut T;
+
+    fn into_par_iter(self) -> Self::Iter {
+        (**self).into_par_iter()
+    }
+}
+
+/// The elements are moved into a `Vec`, whose producer knows how to hand
+/// them out to several threads.
+impl<T: Send, A: Allocator> IntoParallelIterator for SeqList<T, A> {
+    type Iter = vec::IntoIter<T>;
+    type Item = T;
+
+    fn into_par_iter(mut self) -> Self::Iter {
+        let len = self.len();
+        let mut v = Vec::with_capacity(len);
+        unsafe {
+            ptr::copy_nonoverlapping(self.as_ptr(), v.as_mut_ptr(), len);
+            self.set_len(0);
+            v.set_len(len);
+        }
+        v.into_par_iter()
+    }
+}
+
+impl<T: Send, A: Allocator> ParallelExtend<T> for SeqList<T, A> {
+    fn par_extend<I: IntoParallelIterator<Item = T>>(&mut self, par_iter: I) {
+        let v: Vec<T> = par_iter.into_par_iter().collect();
+        self.reserve(v.len());
+        for elem in v {
+            self.push(elem);
+        }
+    }
+}
+
+impl<T: Send, A: Allocator> SeqList<T, A> {
+    pub fn par_sort(&mut self)
+    where
+        T: Ord,
+    {
+        ParallelSliceMut::par_sort(&mut **self)
+    }
+
+    pub fn par_sort_unstable(&mut self)
+    where
+        T: Ord,
+    {
+        ParallelSliceMut::par_sort_unstable(&mut **self)
+    }
+
+    pub fn par_sort_by_key<K: Ord, F: Fn(&T) -> K + Sync>(&mut self, f: F) {
+        ParallelSliceMut::par_sort_by_key(&mut **self, f)
+    }
+}
+
+impl<'a, T: Sync, A: Allocator> IntoParallelIterator for &'a SeqQueue<T, A> {
+    type Iter = Chain<slice::Iter<'a, T>, slice::Iter<'a, T>>;
+    type Item = &'a T;
+
+    fn into_par_iter(self) -> Self::Iter {
+        let (front, back) = self.as_slices();
+        front.into_par_iter().chain(back)
+    }
+}
+
+impl<'a, T: Send, A: Allocator> IntoParallelIterator for &'a mut SeqQueue<T, A> {
+    type Iter = Chain<slice::IterMut<'a, T>, slice::IterMut<'a, T>>;
+    type Item = &'a mut T;
+
+    fn into_par_iter(self) -> Self::Iter {
+        let (front, back) = self.as_mut_slices();
+        front.into_par_iter().chain(back)
+    }
+}
+
+impl<T: Send, A: Allocator> IntoParallelIterator for SeqQueue<T, A> {
+    type Iter = vec::IntoIter<T>;
+    type Item = T;
+
+    fn into_par_iter(mut self) -> Self::Iter {
+        let (front, back) = self.as_slices();
+        let mut v = Vec::with_capacity(front.len() + back.len());
+        unsafe {
+            ptr::copy_nonoverlapping(front.as_ptr(), v.as_mut_ptr(), front.len());
+            ptr::copy_nonoverlapping(back.as_ptr(), v.as_mut_ptr().add(front.len()), back.len());
+            v.set_len(front.len() + back.len());
+            // The elements belong to `v` now.
+            self.forget_elements();
+        }
+        v.into_par_iter()
+    }
+}
+
+impl<T: Send, A: Allocator> ParallelExtend<T> for SeqQueue<T, A> {
+    fn par_extend<I: IntoParallelIterator<Item = T>>(&mut self, par_iter: I) {
+        let v: Vec<T> = par_iter.into_par_iter().collect();
+        for elem in v {
+            self.push(elem);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use rayon::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn test_seq_list() {
+        let mut l: SeqList<i64> = (0..10_000).rev().collect();
+        assert_eq!(l.par_iter().sum::<i64>(), 49_995_000);
+        l.par_iter_mut().for_each(|i| *i *= 2);
+        l.par_sort();
+        assert!(l.iter().copied().eq((0..10_000).map(|i| i * 2)));
+
+        l.par_extend((0..5).into_par_iter());
+        assert_eq!(l.len(), 10_005);
+        assert!(l[10_000..].iter().copied().eq(0..5));
+
+        let v: Vec<i64> = l.into_par_iter().filter(|i| i % 3 == 0).collect();
+        assert_eq!(v.len(), 3_336);
+
+        let rc = Arc::new(());
+        let l: SeqList<_> = (0..100).map(|_| rc.clone()).collect();
+        l.into_par_iter().take_any(10).for_each(drop);
+        assert_eq!(Arc::strong_count(&rc), 1);
+    }
+
+    #[test]
+    fn test_seq_queue() {
+        // Make the ring wrap around.
+        let mut q = SeqQueue::with_capacity(1000);
+        for i in 0..1500 {
+            q.push(i);
+            if i % 2 == 0 {
+                q.pop_front();
+            }
+        }
+        let (front, back) = q.as_slices();
+        assert!(!back.is_empty());
+        assert!(front.iter().chain(back).eq(q.iter()));
+
+        let expected: Vec<i32> = q.iter().copied().collect();
+        let v: Vec<i32> = q.par_iter().copied().collect();
+        assert_eq!(v, expected);
+        q.par_iter_mut().for_each(|i| *i += 1);
+        assert!(q
+            .iter()
+            .eq(expected.iter().map(|i| i + 1).collect::<Vec<_>>().iter()));
+
+        q.par_extend(vec![-1, -2]);
+        assert_eq!(q.peek_back(), Some(&-2));
+        let v: Vec<i32> = q.into_par_iter().collect();
+        assert_eq!(v.len(), expected.len() + 2);
+        assert_eq!(v[0], expected[0] + 1);
+    }
+}
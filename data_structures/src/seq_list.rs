@@ -1,51 +1,137 @@
-use std::{
-    alloc::{self, realloc, Layout},
+use allocator_api2::alloc::{handle_alloc_error, AllocError, Allocator, Global, Layout};
+use core::{
     fmt::Debug,
     marker::PhantomData,
     ops::{Deref, DerefMut},
     ptr::{self, NonNull},
 };
 
-use super::List;
+use crate::{partition_slice, List};
 
-pub struct SeqList<T> {
+pub struct SeqList<T, A: Allocator = Global> {
     ptr: NonNull<T>,
     capacity: usize,
     len: usize,
+    alloc: A,
     marker: PhantomData<T>,
 }
 
 impl<T> SeqList<T> {
     pub fn new() -> Self {
+        Self::new_in(Global)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_in(capacity, Global)
+    }
+}
+
+impl<T, A: Allocator> SeqList<T, A> {
+    pub fn new_in(alloc: A) -> Self {
         SeqList {
             ptr: NonNull::dangling(),
             capacity: 0,
             len: 0,
+            alloc,
             marker: PhantomData,
         }
     }
 
+    pub fn with_capacity_in(capacity: usize, alloc: A) -> Self {
+        let mut l = Self::new_in(alloc);
+        if capacity > 0 {
+            l.grow_to(capacity);
+        }
+        l
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn allocator(&self) -> &A {
+        &self.alloc
+    }
+
+    pub fn push(&mut self, elem: T) {
+        if self.len == self.capacity {
+            self.grow();
+        }
+        unsafe { ptr::write(self.ptr.as_ptr().add(self.len), elem) }
+        self.len += 1;
+    }
+
+    /// Like `push`, but `elem` is dropped and an error returned instead of
+    /// aborting if the list cannot grow.
+    pub fn try_push(&mut self, elem: T) -> Result<(), AllocError> {
+        if self.len == self.capacity {
+            self.try_reserve(1)?;
+        }
+        unsafe { ptr::write(self.ptr.as_ptr().add(self.len), elem) }
+        self.len += 1;
+        Ok(())
+    }
+
+    pub fn reserve(&mut self, additional: usize) {
+        let needed = self
+            .len
+            .checked_add(additional)
+            .expect("Allocation too large");
+        if needed > self.capacity {
+            self.grow_to(needed.max(self.capacity * 2));
+        }
+    }
+
+    /// Makes room for at least `additional` more elements.
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), AllocError> {
+        let needed = self.len.checked_add(additional).ok_or(AllocError)?;
+        if needed > self.capacity {
+            self.try_grow_to(needed.max(self.capacity * 2))?;
+        }
+        Ok(())
+    }
+
     fn grow(&mut self) {
-        let new_capacity: usize;
-        let new_ptr: *mut u8;
-        let new_layout: Layout;
+        let new_capacity = if self.capacity == 0 {
+            1
+        } else {
+            self.capacity * 2
+        };
+        self.grow_to(new_capacity);
+    }
 
-        if self.capacity == 0 {
-            new_capacity = 1;
-            new_layout = Layout::array::<T>(1).unwrap();
-            new_ptr = unsafe { alloc::alloc(new_layout) };
+    fn grow_to(&mut self, new_capacity: usize) {
+        let new_layout = Layout::array::<T>(new_capacity).expect("Allocation too large");
+        if self.try_grow_to(new_capacity).is_err() {
+            handle_alloc_error(new_layout)
+        }
+    }
+
+    fn try_grow_to(&mut self, new_capacity: usize) -> Result<(), AllocError> {
+        let new_layout = Layout::array::<T>(new_capacity).map_err(|_| AllocError)?;
+
+        let new_ptr = if self.capacity == 0 {
+            self.alloc.allocate(new_layout)?
         } else {
-            new_capacity = self.capacity * 2;
-            new_layout = Layout::array::<T>(new_capacity).unwrap();
-            assert!(new_capacity <= isize::MAX as usize, "Allocation too large");
             let old_layout = Layout::array::<T>(self.capacity).unwrap();
-            new_ptr =
-                unsafe { realloc(self.ptr.as_ptr() as *mut u8, old_layout, new_layout.size()) }
+            unsafe { self.alloc.grow(self.ptr.cast(), old_layout, new_layout)? }
         };
 
-        self.ptr = NonNull::new(new_ptr as *mut T)
-            .unwrap_or_else(|| alloc::handle_alloc_error(new_layout));
+        self.ptr = new_ptr.cast();
         self.capacity = new_capacity;
+        Ok(())
+    }
+
+    /// A pointer to the buffer, valid for `capacity` elements.
+    pub fn as_mut_ptr(&mut self) -> *mut T {
+        self.ptr.as_ptr()
+    }
+
+    /// # Safety
+    /// The first `len` elements should be initialised and `len` should not
+    /// exceed the capacity.
+    pub unsafe fn set_len(&mut self, len: usize) {
+        self.len = len;
     }
 
     pub fn pop(&mut self) -> Option<T> {
@@ -59,37 +145,30 @@ impl<T> SeqList<T> {
     }
 
     pub fn concatenate(&mut self, mut list: Self) {
-        while self.len + list.len > self.capacity {
-            self.grow()
-        }
+        self.reserve(list.len);
         unsafe {
             ptr::copy_nonoverlapping(list.ptr.as_ptr(), self.ptr.as_ptr().add(self.len), list.len);
             self.len += list.len;
         }
-        let layout = Layout::array::<T>(list.capacity).unwrap();
-        unsafe { alloc::dealloc(list.ptr.as_ptr() as *mut u8, layout) }
         list.len = 0;
     }
 
     /// # Safety
     /// pos should not be out of range
     pub unsafe fn insert_list(&mut self, mut list: Self, pos: usize) {
-        while self.len + list.len > self.capacity {
-            self.grow();
-        }
-        ptr::copy_nonoverlapping(
-            list.ptr.as_ptr().add(pos),
-            list.ptr.as_ptr().add(pos).add(list.len),
+        self.reserve(list.len);
+        ptr::copy(
+            self.ptr.as_ptr().add(pos),
+            self.ptr.as_ptr().add(pos).add(list.len),
             self.len - pos,
         );
         ptr::copy_nonoverlapping(list.ptr.as_ptr(), self.ptr.as_ptr().add(pos), list.len);
-        let layout = Layout::array::<T>(list.capacity).unwrap();
-        alloc::dealloc(list.ptr.as_ptr() as *mut u8, layout);
+        self.len += list.len;
         list.len = 0;
     }
 }
 
-impl<'a, T> std::iter::IntoIterator for &'a SeqList<T> {
+impl<'a, T, A: Allocator> IntoIterator for &'a SeqList<T, A> {
     type Item = &'a T;
 
     type IntoIter = core::slice::Iter<'a, T>;
@@ -99,106 +178,55 @@ impl<'a, T> std::iter::IntoIterator for &'a SeqList<T> {
     }
 }
 
-impl<T> List<T> for SeqList<T> {
+impl<T, A: Allocator + Default> List<T> for SeqList<T, A> {
+    #[inline]
     fn push(&mut self, elem: T) {
-        if self.len == self.capacity {
-            self.grow();
-        }
-        unsafe { ptr::write(self.ptr.as_ptr().add(self.len), elem) }
-        self.len += 1;
+        SeqList::push(self, elem)
     }
 
-    fn partition(self) -> Self
+    fn partition(mut self) -> Self
     where
         T: PartialOrd,
     {
-        if self.len == 0 {
-            return self;
-        }
-        unsafe {
-            let mut l = self.ptr.as_ptr().add(1);
-            let mut r = self.ptr.as_ptr().add(self.len - 1);
-            let first = &self[0];
-
-            loop {
-                if l.offset_from(r) > 0 {
-                    ptr::swap(self.ptr.as_ptr(), r);
-                    return self;
-                }
-                if *l < *first {
-                    l = l.add(1);
-                } else {
-                    break;
-                }
-            }
-
-            loop {
-                if r.offset_from(self.ptr.as_ptr()) <= 0 {
-                    return self;
-                }
-                if *r >= *first {
-                    r = r.sub(1);
-                } else {
-                    break;
-                }
-            }
-
-            loop {
-                while *l < *first {
-                    l = l.add(1)
-                }
-
-                while *r >= *first {
-                    r = r.sub(1)
-                }
-
-                if l >= r {
-                    break;
-                }
-                ptr::swap(l, r);
-                l = l.add(1);
-                r = r.sub(1);
-            }
-            ptr::swap(self.ptr.as_ptr(), r);
-            self
-        }
+        partition_slice(&mut self);
+        self
     }
 }
 
-impl<T> Default for SeqList<T> {
+impl<T, A: Allocator + Default> Default for SeqList<T, A> {
     fn default() -> Self {
-        Self::new()
+        Self::new_in(A::default())
     }
 }
 
-impl<T> Drop for SeqList<T> {
+impl<T, A: Allocator> Drop for SeqList<T, A> {
     fn drop(&mut self) {
-        if self.len == 0 {
+        while self.pop().is_some() {}
+        if self.capacity == 0 {
             return;
         }
-        while self.pop().is_some() {}
         let layout = Layout::array::<T>(self.capacity).unwrap();
-        unsafe { alloc::dealloc(self.ptr.as_ptr() as *mut u8, layout) }
+        unsafe { self.alloc.deallocate(self.ptr.cast(), layout) }
     }
 }
 
-impl<T> Deref for SeqList<T> {
+impl<T, A: Allocator> Deref for SeqList<T, A> {
     type Target = [T];
 
     fn deref(&self) -> &Self::Target {
-        unsafe { std::slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+        unsafe { core::slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
     }
 }
 
-impl<T> DerefMut for SeqList<T> {
+impl<T, A: Allocator> DerefMut for SeqList<T, A> {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        unsafe { std::slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+        unsafe { core::slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
     }
 }
 
-impl<T> FromIterator<T> for SeqList<T> {
+impl<T, A: Allocator + Default> FromIterator<T> for SeqList<T, A> {
     fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
-        let mut l = SeqList::new();
+        let mut l = SeqList::default();
         for i in iter {
             l.push(i);
         }
@@ -206,11 +234,11 @@ impl<T> FromIterator<T> for SeqList<T> {
     }
 }
 
-impl<T> Debug for SeqList<T>
+impl<T, A: Allocator> Debug for SeqList<T, A>
 where
     T: Debug,
 {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         for i in self.iter() {
             write!(f, "{:?} ", i)?
         }
@@ -221,6 +249,7 @@ where
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::test;
 
     #[test]
     fn test() {
@@ -237,4 +266,34 @@ mod test {
     fn test_partition() {
         super::super::test::test_partition::<SeqList<i32>>()
     }
+
+    #[test]
+    fn test_insert_list() {
+        let mut l: SeqList<i32> = (0..5).collect();
+        unsafe { l.insert_list((10..13).collect(), 2) };
+        assert_eq!(l[..], [0, 1, 10, 11, 12, 2, 3, 4]);
+        unsafe { l.insert_list((20..22).collect(), 8) };
+        assert_eq!(l[..], [0, 1, 10, 11, 12, 2, 3, 4, 20, 21]);
+    }
+
+    #[test]
+    fn test_allocator() {
+        let alloc = test::CountingAllocator::default();
+        let mut l = SeqList::new_in(&alloc);
+        for i in 0..100 {
+            l.push(i);
+        }
+        l.try_reserve(100).unwrap();
+        assert!(l.capacity() >= 200);
+        assert!(l.try_reserve(usize::MAX).is_err());
+        assert!(alloc.live() > 0);
+        drop(l);
+        assert_eq!(alloc.live(), 0);
+
+        let mut l = SeqList::new_in(test::FailingAllocator);
+        assert_eq!(l.try_push(1), Err(AllocError));
+        assert!(l.is_empty());
+        let mut l = SeqList::<i32, _>::with_capacity_in(0, test::FailingAllocator);
+        assert!(l.try_reserve(1).is_err());
+    }
 }
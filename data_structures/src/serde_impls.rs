@@ -0,0 +1,214 @@
+//! `Serialize` and `Deserialize` for the containers, which all look like
+//! sequences to serde. Queues are written from the front to the back and
+//! stacks from the bottom to the top.
+
+use core::{fmt, marker::PhantomData};
+
+use allocator_api2::alloc::Allocator;
+use serde::{
+    de::{SeqAccess, Visitor},
+    ser::SerializeSeq,
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use crate::{
+    cir_linked_list::CirLinkedList,
+    linked_list::LinkedList,
+    queue::SeqQueue,
+    seq_list::SeqList,
+    stack::{SeqStack, Stack, StackStorage},
+};
+
+// Don't trust the size hint of the input too far when preallocating.
+const MAX_PREALLOCATED: usize = 4096;
+
+fn serialize_seq<'a, S, T, I>(serializer: S, len: usize, iter: I) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    T: Serialize + 'a,
+    I: IntoIterator<Item = &'a T>,
+{
+    let mut seq = serializer.serialize_seq(Some(len))?;
+    for elem in iter {
+        seq.serialize_element(elem)?;
+    }
+    seq.end()
+}
+
+struct SeqVisitor<C, T> {
+    with_capacity: fn(usize) -> C,
+    push: fn(&mut C, T),
+    marker: PhantomData<T>,
+}
+
+impl<'de, C, T: Deserialize<'de>> Visitor<'de> for SeqVisitor<C, T> {
+    type Value = C;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a sequence")
+    }
+
+    fn visit_seq<S: SeqAccess<'de>>(self, mut seq: S) -> Result<C, S::Error> {
+        let capacity = seq.size_hint().unwrap_or(0).min(MAX_PREALLOCATED);
+        let mut container = (self.with_capacity)(capacity);
+        while let Some(elem) = seq.next_element()? {
+            (self.push)(&mut container, elem);
+        }
+        Ok(container)
+    }
+}
+
+fn deserialize_seq<'de, D, C, T>(
+    deserializer: D,
+    with_capacity: fn(usize) -> C,
+    push: fn(&mut C, T),
+) -> Result<C, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    deserializer.deserialize_seq(SeqVisitor {
+        with_capacity,
+        push,
+        marker: PhantomData,
+    })
+}
+
+impl<T: Serialize, A: Allocator> Serialize for SeqList<T, A> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_seq(serializer, self.len(), self.iter())
+    }
+}
+
+impl<'de, T: Deserialize<'de>, A: Allocator + Default> Deserialize<'de> for SeqList<T, A> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_seq(
+            deserializer,
+            |capacity| SeqList::with_capacity_in(capacity, A::default()),
+            SeqList::push,
+        )
+    }
+}
+
+impl<T: Serialize, A: Allocator + Clone> Serialize for LinkedList<T, A> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_seq(serializer, self.into_iter().count(), self)
+    }
+}
+
+impl<'de, T: Deserialize<'de>, A: Allocator + Clone + Default> Deserialize<'de>
+    for LinkedList<T, A>
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_seq(
+            deserializer,
+            |_| LinkedList::new_in(A::default()),
+            LinkedList::push,
+        )
+    }
+}
+
+impl<T: Serialize, A: Allocator> Serialize for CirLinkedList<T, A> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_seq(serializer, self.iter().count(), self)
+    }
+}
+
+impl<'de, T: Deserialize<'de>, A: Allocator + Default> Deserialize<'de> for CirLinkedList<T, A> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_seq(
+            deserializer,
+            |_| CirLinkedList::new_in(A::default()),
+            CirLinkedList::push,
+        )
+    }
+}
+
+impl<T: Serialize, L: StackStorage<T>> Serialize for SeqStack<T, L> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_seq(serializer, self.len(), self)
+    }
+}
+
+impl<'de, T: Deserialize<'de>, L: StackStorage<T>> Deserialize<'de> for SeqStack<T, L> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_seq(deserializer, SeqStack::with_capacity, SeqStack::push)
+    }
+}
+
+impl<T: Serialize, A: Allocator> Serialize for SeqQueue<T, A> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_seq(serializer, self.len(), self.iter())
+    }
+}
+
+impl<'de, T: Deserialize<'de>, A: Allocator + Default> Deserialize<'de> for SeqQueue<T, A> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_seq(
+            deserializer,
+            |capacity| SeqQueue::with_capacity_in(capacity, A::default()),
+            SeqQueue::push,
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn round_trip<C>(container: &C) -> (C, C)
+    where
+        C: Serialize + for<'de> Deserialize<'de>,
+    {
+        let json = serde_json::to_string(container).unwrap();
+        let bytes = bincode::serialize(container).unwrap();
+        (
+            serde_json::from_str(&json).unwrap(),
+            bincode::deserialize(&bytes).unwrap(),
+        )
+    }
+
+    fn assert_round_trip<C>(container: C, expected: &[i32])
+    where
+        C: Serialize + for<'de> Deserialize<'de>,
+        for<'a> &'a C: IntoIterator<Item = &'a i32>,
+    {
+        assert_eq!(
+            serde_json::to_string(&container).unwrap(),
+            serde_json::to_string(expected).unwrap()
+        );
+        let (from_json, from_bincode) = round_trip(&container);
+        assert!(from_json.into_iter().eq(expected));
+        assert!(from_bincode.into_iter().eq(expected));
+    }
+
+    #[test]
+    fn test_lists() {
+        for v in [vec![], vec![1], vec![3, 1, 2]] {
+            assert_round_trip(v.iter().copied().collect::<SeqList<i32>>(), &v);
+            assert_round_trip(v.iter().copied().collect::<LinkedList<i32>>(), &v);
+            assert_round_trip(v.iter().copied().collect::<CirLinkedList<i32>>(), &v);
+        }
+    }
+
+    #[test]
+    fn test_stack_and_queue() {
+        let mut s = SeqStack::<i32>::new();
+        for i in 1..=3 {
+            s.push(i);
+        }
+        assert_round_trip(s, &[1, 2, 3]);
+
+        // Make the ring wrap around.
+        let mut q = SeqQueue::with_capacity(3);
+        for i in 0..5 {
+            q.push(i);
+            if i % 2 == 0 {
+                q.pop_front();
+            }
+        }
+        assert_round_trip(q, &[3, 4]);
+
+        assert!(serde_json::from_str::<SeqQueue<i32>>("{}").is_err());
+    }
+}
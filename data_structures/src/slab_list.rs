@@ -0,0 +1,502 @@
+use core::fmt::Debug;
+
+use crate::{seq_list::SeqList, List};
+
+const NIL: u32 = u32::MAX;
+
+/// Refers to an element pushed into a [`SlabList`]. A handle becomes stale
+/// once its element is removed and is never reused for another element: a
+/// node is retired rather than reused once its generation runs out.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Handle {
+    index: u32,
+    generation: u32,
+}
+
+struct Node<T> {
+    elem: Option<T>,
+    generation: u32,
+    // A vacant node keeps the next vacant one in `next`.
+    next: u32,
+    prev: u32,
+}
+
+/// A doubly linked list whose nodes live side by side in a `SeqList` and
+/// link to each other by index.
+pub struct SlabList<T> {
+    nodes: SeqList<Node<T>>,
+    head: u32,
+    tail: u32,
+    free: u32,
+    len: usize,
+}
+
+impl<T> SlabList<T> {
+    pub fn new() -> Self {
+        SlabList {
+            nodes: SeqList::new(),
+            head: NIL,
+            tail: NIL,
+            free: NIL,
+            len: 0,
+        }
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        SlabList {
+            nodes: SeqList::with_capacity(capacity),
+            ..Self::new()
+        }
+    }
+
+    pub fn push_back(&mut self, elem: T) -> Handle {
+        let index = self.alloc_node(elem);
+        self.link_after(index, self.tail);
+        self.handle(index)
+    }
+
+    pub fn push_front(&mut self, elem: T) -> Handle {
+        let index = self.alloc_node(elem);
+        self.link_before(index, self.head);
+        self.handle(index)
+    }
+
+    /// Inserts `elem` right after the element `handle` refers to, or returns
+    /// `None` if the handle is stale.
+    pub fn insert_after(&mut self, handle: Handle, elem: T) -> Option<Handle> {
+        self.node(handle)?;
+        let index = self.alloc_node(elem);
+        self.link_after(index, handle.index);
+        Some(self.handle(index))
+    }
+
+    pub fn insert_before(&mut self, handle: Handle, elem: T) -> Option<Handle> {
+        self.node(handle)?;
+        let index = self.alloc_node(elem);
+        self.link_before(index, handle.index);
+        Some(self.handle(index))
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        if self.head == NIL {
+            None
+        } else {
+            Some(self.remove_at(self.head))
+        }
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        if self.tail == NIL {
+            None
+        } else {
+            Some(self.remove_at(self.tail))
+        }
+    }
+
+    pub fn remove(&mut self, handle: Handle) -> Option<T> {
+        self.node(handle)?;
+        Some(self.remove_at(handle.index))
+    }
+
+    pub fn contains(&self, handle: Handle) -> bool {
+        self.node(handle).is_some()
+    }
+
+    pub fn get(&self, handle: Handle) -> Option<&T> {
+        self.node(handle)?.elem.as_ref()
+    }
+
+    pub fn get_mut(&mut self, handle: Handle) -> Option<&mut T> {
+        let node = self.nodes.get_mut(handle.index as usize)?;
+        if node.generation == handle.generation {
+            node.elem.as_mut()
+        } else {
+            None
+        }
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.elem_at(self.head)
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        self.elem_at(self.tail)
+    }
+
+    pub fn front_handle(&self) -> Option<Handle> {
+        (self.head != NIL).then(|| self.handle(self.head))
+    }
+
+    pub fn back_handle(&self) -> Option<Handle> {
+        (self.tail != NIL).then(|| self.handle(self.tail))
+    }
+
+    /// The handle of the element after the one `handle` refers to.
+    pub fn next(&self, handle: Handle) -> Option<Handle> {
+        let next = self.node(handle)?.next;
+        (next != NIL).then(|| self.handle(next))
+    }
+
+    pub fn prev(&self, handle: Handle) -> Option<Handle> {
+        let prev = self.node(handle)?.prev;
+        (prev != NIL).then(|| self.handle(prev))
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn clear(&mut self) {
+        while self.pop_back().is_some() {}
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            list: self,
+            front: self.head,
+            back: self.tail,
+            len: self.len,
+        }
+    }
+
+    fn handle(&self, index: u32) -> Handle {
+        Handle {
+            index,
+            generation: self.nodes[index as usize].generation,
+        }
+    }
+
+    fn node(&self, handle: Handle) -> Option<&Node<T>> {
+        let node = self.nodes.get(handle.index as usize)?;
+        (node.generation == handle.generation && node.elem.is_some()).then_some(node)
+    }
+
+    fn elem_at(&self, index: u32) -> Option<&T> {
+        self.nodes.get(index as usize)?.elem.as_ref()
+    }
+
+    fn alloc_node(&mut self, elem: T) -> u32 {
+        self.len += 1;
+        if self.free != NIL {
+            let index = self.free;
+            let node = &mut self.nodes[index as usize];
+            self.free = node.next;
+            node.elem = Some(elem);
+            index
+        } else {
+            let index = self.nodes.len();
+            assert!(
+                index < NIL as usize,
+                "A SlabList holds at most u32::MAX - 1 nodes"
+            );
+            self.nodes.push(Node {
+                elem: Some(elem),
+                generation: 0,
+                next: NIL,
+                prev: NIL,
+            });
+            index as u32
+        }
+    }
+
+    /// Links the unlinked node `index` after `prev`, or at the front if
+    /// `prev` is `NIL`.
+    fn link_after(&mut self, index: u32, prev: u32) {
+        let next = if prev == NIL {
+            self.head
+        } else {
+            self.nodes[prev as usize].next
+        };
+        self.link_between(index, prev, next);
+    }
+
+    fn link_before(&mut self, index: u32, next: u32) {
+        let prev = if next == NIL {
+            self.tail
+        } else {
+            self.nodes[next as usize].prev
+        };
+        self.link_between(index, prev, next);
+    }
+
+    fn link_between(&mut self, index: u32, prev: u32, next: u32) {
+        let node = &mut self.nodes[index as usize];
+        node.prev = prev;
+        node.next = next;
+        if prev == NIL {
+            self.head = index;
+        } else {
+            self.nodes[prev as usize].next = index;
+        }
+        if next == NIL {
+            self.tail = index;
+        } else {
+            self.nodes[next as usize].prev = index;
+        }
+    }
+
+    fn unlink(&mut self, index: u32) {
+        let Node { prev, next, .. } = self.nodes[index as usize];
+        if prev == NIL {
+            self.head = next;
+        } else {
+            self.nodes[prev as usize].next = next;
+        }
+        if next == NIL {
+            self.tail = prev;
+        } else {
+            self.nodes[next as usize].prev = prev;
+        }
+    }
+
+    fn remove_at(&mut self, index: u32) -> T {
+        self.unlink(index);
+        self.len -= 1;
+        let node = &mut self.nodes[index as usize];
+        node.prev = NIL;
+        node.next = NIL;
+        // A node whose generation would wrap is retired instead.
+        if let Some(generation) = node.generation.checked_add(1) {
+            node.generation = generation;
+            node.next = self.free;
+            self.free = index;
+        }
+        node.elem.take().unwrap()
+    }
+}
+
+impl<T> Default for SlabList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> List<T> for SlabList<T> {
+    #[inline]
+    fn push(&mut self, elem: T) {
+        self.push_back(elem);
+    }
+
+    /// Relinks the nodes in place, so every handle stays valid.
+    fn partition(mut self) -> Self
+    where
+        T: PartialOrd,
+    {
+        let first = self.head;
+        if first == NIL {
+            return self;
+        }
+        let mut curr = self.nodes[first as usize].next;
+        let mut le_tail = NIL;
+        let mut geq_tail = first;
+        self.nodes[first as usize].prev = NIL;
+        self.head = NIL;
+        while curr != NIL {
+            let next = self.nodes[curr as usize].next;
+            let nodes = &self.nodes;
+            if nodes[curr as usize].elem < nodes[first as usize].elem {
+                self.link_after_tail(curr, &mut le_tail);
+                if self.head == NIL {
+                    self.head = curr;
+                }
+            } else {
+                self.link_after_tail(curr, &mut geq_tail);
+            }
+            curr = next;
+        }
+        if le_tail == NIL {
+            self.head = first;
+        } else {
+            self.nodes[le_tail as usize].next = first;
+            self.nodes[first as usize].prev = le_tail;
+        }
+        self.nodes[geq_tail as usize].next = NIL;
+        self.tail = geq_tail;
+        self
+    }
+}
+
+impl<T> SlabList<T> {
+    fn link_after_tail(&mut self, index: u32, tail: &mut u32) {
+        self.nodes[index as usize].prev = *tail;
+        if *tail != NIL {
+            self.nodes[*tail as usize].next = index;
+        }
+        *tail = index;
+    }
+}
+
+impl<T> FromIterator<T> for SlabList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut l = Self::new();
+        for i in iter {
+            l.push_back(i);
+        }
+        l
+    }
+}
+
+impl<'a, T> IntoIterator for &'a SlabList<T> {
+    type Item = &'a T;
+
+    type IntoIter = Iter<'a, T>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+pub struct Iter<'a, T> {
+    list: &'a SlabList<T>,
+    front: u32,
+    back: u32,
+    len: usize,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        let node = &self.list.nodes[self.front as usize];
+        self.front = node.next;
+        node.elem.as_ref()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for Iter<'a, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        let node = &self.list.nodes[self.back as usize];
+        self.back = node.prev;
+        node.elem.as_ref()
+    }
+}
+
+impl<'a, T> ExactSizeIterator for Iter<'a, T> {}
+
+#[cfg(feature = "nightly")]
+unsafe impl<'a, T> core::iter::TrustedLen for Iter<'a, T> {}
+
+impl<T: Debug> Debug for SlabList<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        for i in self.iter() {
+            write!(f, "{:?} ", i)?
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::VecDeque;
+
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn test() {
+        let mut l = SlabList::new();
+        let two = l.push_back(2);
+        let one = l.push_front(1);
+        let four = l.push_back(4);
+        let three = l.insert_before(four, 3).unwrap();
+        assert!(l.iter().eq([1, 2, 3, 4].iter()));
+        assert!(l.iter().rev().eq([4, 3, 2, 1].iter()));
+        assert_eq!(l.front_handle(), Some(one));
+        assert_eq!(l.next(two), Some(three));
+        assert_eq!(l.prev(two), Some(one));
+
+        assert_eq!(l.remove(two), Some(2));
+        assert_eq!(l.remove(two), None);
+        assert!(!l.contains(two));
+        assert_eq!(l.next(one), Some(three));
+        // The freed node is reused, but the old handle stays stale.
+        let five = l.insert_after(four, 5).unwrap();
+        assert_eq!(l.get(two), None);
+        assert_eq!(l.get(five), Some(&5));
+        *l.get_mut(three).unwrap() = 30;
+        assert!(l.iter().eq([1, 30, 4, 5].iter()));
+
+        assert_eq!(l.pop_front(), Some(1));
+        assert_eq!(l.pop_back(), Some(5));
+        assert_eq!(l.front(), Some(&30));
+        assert_eq!(l.back(), Some(&4));
+        assert_eq!(l.len(), 2);
+        l.clear();
+        assert!(l.is_empty());
+        assert!(l.pop_back().is_none());
+        assert!(l.iter().next().is_none());
+    }
+
+    #[test]
+    fn test_retired_node() {
+        let mut l = SlabList::new();
+        let one = l.push_back(1);
+        l.nodes[one.index as usize].generation = u32::MAX;
+        let one = l.front_handle().unwrap();
+        assert_eq!(l.remove(one), Some(1));
+        let two = l.push_back(2);
+        assert_ne!(two.index, one.index);
+        assert_eq!(l.get(one), None);
+        assert!(l.iter().eq([2].iter()));
+    }
+
+    #[test]
+    fn test_random() {
+        let mut rng = rand::thread_rng();
+        let mut l = SlabList::new();
+        let mut model: VecDeque<(Handle, i32)> = VecDeque::new();
+        for i in 0..2000 {
+            match rng.gen_range(0..5) {
+                0 => model.push_back((l.push_back(i), i)),
+                1 => model.push_front((l.push_front(i), i)),
+                2 => assert_eq!(l.pop_front(), model.pop_front().map(|(_, i)| i)),
+                3 => assert_eq!(l.pop_back(), model.pop_back().map(|(_, i)| i)),
+                _ if !model.is_empty() => {
+                    let (handle, elem) = model.remove(rng.gen_range(0..model.len())).unwrap();
+                    assert_eq!(l.remove(handle), Some(elem));
+                }
+                _ => {}
+            }
+            assert_eq!(l.len(), model.len());
+        }
+        assert!(l.iter().eq(model.iter().map(|(_, i)| i)));
+        for (handle, elem) in &model {
+            assert_eq!(l.get(*handle), Some(elem));
+        }
+    }
+
+    #[test]
+    fn test_partition() {
+        super::super::test::test_partition::<SlabList<i32>>();
+
+        let mut l = SlabList::new();
+        let handles: Vec<_> = [3, 5, 1, 4, 2]
+            .into_iter()
+            .map(|i| l.push_back(i))
+            .collect();
+        let l = l.partition();
+        for (handle, i) in handles.into_iter().zip([3, 5, 1, 4, 2]) {
+            assert_eq!(l.get(handle), Some(&i));
+        }
+        let v: Vec<_> = l.iter().rev().copied().collect();
+        assert_eq!(v.len(), 5);
+        assert!(super::super::test::is_partitioned(v.iter().rev(), |&i| i < 3));
+    }
+}
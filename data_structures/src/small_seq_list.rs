@@ -0,0 +1,287 @@
+use allocator_api2::alloc::AllocError;
+use core::{
+    fmt::Debug,
+    mem::MaybeUninit,
+    ops::{Deref, DerefMut},
+    ptr,
+};
+
+use crate::{partition_slice, seq_list::SeqList, List};
+
+enum Storage<T, const N: usize> {
+    Inline([MaybeUninit<T>; N], usize),
+    Heap(SeqList<T>),
+}
+
+/// A `SeqList` that keeps up to `N` elements inline and only moves them to
+/// the heap once it outgrows that.
+pub struct SmallSeqList<T, const N: usize> {
+    storage: Storage<T, N>,
+}
+
+impl<T, const N: usize> SmallSeqList<T, N> {
+    pub fn new() -> Self {
+        SmallSeqList {
+            storage: Storage::Inline([const { MaybeUninit::uninit() }; N], 0),
+        }
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        let mut l = Self::new();
+        l.reserve(capacity);
+        l
+    }
+
+    pub fn capacity(&self) -> usize {
+        match &self.storage {
+            Storage::Inline(..) => N,
+            Storage::Heap(heap) => heap.capacity(),
+        }
+    }
+
+    /// Whether the elements have been moved to the heap.
+    pub fn spilled(&self) -> bool {
+        matches!(self.storage, Storage::Heap(_))
+    }
+
+    pub fn push(&mut self, elem: T) {
+        if self.len() == N && !self.spilled() {
+            self.reserve(1);
+        }
+        match &mut self.storage {
+            Storage::Inline(buf, len) => {
+                buf[*len].write(elem);
+                *len += 1;
+            }
+            Storage::Heap(heap) => heap.push(elem),
+        }
+    }
+
+    /// Like `push`, but `elem` is dropped and an error returned instead of
+    /// aborting if the list cannot grow.
+    pub fn try_push(&mut self, elem: T) -> Result<(), AllocError> {
+        if self.len() == self.capacity() {
+            self.try_reserve(1)?;
+        }
+        self.push(elem);
+        Ok(())
+    }
+
+    pub fn reserve(&mut self, additional: usize) {
+        match &mut self.storage {
+            Storage::Inline(_, len) => {
+                let needed = len.checked_add(additional).expect("Allocation too large");
+                if needed > N {
+                    self.spill(SeqList::with_capacity(needed.max(N * 2)));
+                }
+            }
+            Storage::Heap(heap) => heap.reserve(additional),
+        }
+    }
+
+    /// Makes room for at least `additional` more elements.
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), AllocError> {
+        match &mut self.storage {
+            Storage::Inline(_, len) => {
+                let needed = len.checked_add(additional).ok_or(AllocError)?;
+                if needed > N {
+                    let mut heap = SeqList::new();
+                    heap.try_reserve(needed.max(N * 2))?;
+                    self.spill(heap);
+                }
+                Ok(())
+            }
+            Storage::Heap(heap) => heap.try_reserve(additional),
+        }
+    }
+
+    /// Moves the inline elements into `heap`, which is empty and has room
+    /// for them.
+    fn spill(&mut self, mut heap: SeqList<T>) {
+        if let Storage::Inline(buf, len) = &mut self.storage {
+            unsafe {
+                ptr::copy_nonoverlapping(buf.as_ptr().cast(), heap.as_mut_ptr(), *len);
+                heap.set_len(*len);
+            }
+            *len = 0;
+            self.storage = Storage::Heap(heap);
+        }
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        match &mut self.storage {
+            Storage::Inline(_, 0) => None,
+            Storage::Inline(buf, len) => {
+                *len -= 1;
+                Some(unsafe { buf[*len].assume_init_read() })
+            }
+            Storage::Heap(heap) => heap.pop(),
+        }
+    }
+
+    pub fn concatenate(&mut self, mut list: Self) {
+        self.reserve(list.len());
+        let len = self.len();
+        let other_len = list.len();
+        unsafe {
+            ptr::copy_nonoverlapping(list.as_ptr(), self.as_mut_ptr().add(len), other_len);
+            self.set_len(len + other_len);
+            list.set_len(0);
+        }
+    }
+
+    /// A pointer to the buffer, valid for `capacity` elements.
+    pub fn as_mut_ptr(&mut self) -> *mut T {
+        match &mut self.storage {
+            Storage::Inline(buf, _) => buf.as_mut_ptr().cast(),
+            Storage::Heap(heap) => heap.as_mut_ptr(),
+        }
+    }
+
+    /// # Safety
+    /// The first `len` elements should be initialised and `len` should not
+    /// exceed the capacity.
+    pub unsafe fn set_len(&mut self, new_len: usize) {
+        match &mut self.storage {
+            Storage::Inline(_, len) => *len = new_len,
+            Storage::Heap(heap) => heap.set_len(new_len),
+        }
+    }
+}
+
+impl<T, const N: usize> Default for SmallSeqList<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> Drop for SmallSeqList<T, N> {
+    fn drop(&mut self) {
+        if let Storage::Inline(..) = self.storage {
+            while self.pop().is_some() {}
+        }
+    }
+}
+
+impl<T, const N: usize> Deref for SmallSeqList<T, N> {
+    type Target = [T];
+
+    fn deref(&self) -> &Self::Target {
+        match &self.storage {
+            Storage::Inline(buf, len) => unsafe {
+                core::slice::from_raw_parts(buf.as_ptr().cast(), *len)
+            },
+            Storage::Heap(heap) => heap,
+        }
+    }
+}
+
+impl<T, const N: usize> DerefMut for SmallSeqList<T, N> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        match &mut self.storage {
+            Storage::Inline(buf, len) => unsafe {
+                core::slice::from_raw_parts_mut(buf.as_mut_ptr().cast(), *len)
+            },
+            Storage::Heap(heap) => heap,
+        }
+    }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a SmallSeqList<T, N> {
+    type Item = &'a T;
+
+    type IntoIter = core::slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T, const N: usize> List<T> for SmallSeqList<T, N> {
+    #[inline]
+    fn push(&mut self, elem: T) {
+        SmallSeqList::push(self, elem)
+    }
+
+    fn partition(mut self) -> Self
+    where
+        T: PartialOrd,
+    {
+        partition_slice(&mut self);
+        self
+    }
+}
+
+impl<T, const N: usize> FromIterator<T> for SmallSeqList<T, N> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut l = Self::new();
+        for i in iter {
+            l.push(i);
+        }
+        l
+    }
+}
+
+impl<T: Debug, const N: usize> Debug for SmallSeqList<T, N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        for i in self.iter() {
+            write!(f, "{:?} ", i)?
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::rc::Rc;
+
+    use super::*;
+
+    #[test]
+    fn test() {
+        let mut l = SmallSeqList::<i32, 4>::new();
+        for i in 0..4 {
+            l.push(i);
+        }
+        assert!(!l.spilled());
+        assert_eq!(l.capacity(), 4);
+        l.push(4);
+        assert!(l.spilled());
+        assert_eq!(*l, [0, 1, 2, 3, 4]);
+        assert_eq!(l.pop(), Some(4));
+        l[0] = 10;
+        assert!(l.iter().eq([10, 1, 2, 3].iter()));
+
+        let mut l = SmallSeqList::<i32, 8>::with_capacity(2);
+        assert!(!l.spilled());
+        l.concatenate((1..=3).collect());
+        l.concatenate((4..=10).collect());
+        assert!(l.spilled());
+        assert!(l.iter().eq((1..=10).collect::<Vec<_>>().iter()));
+
+        let mut l = SmallSeqList::<i32, 0>::new();
+        assert!(l.pop().is_none());
+        l.push(1);
+        assert_eq!(l.pop(), Some(1));
+    }
+
+    #[test]
+    fn test_drop() {
+        let rc = Rc::new(());
+        let l: SmallSeqList<_, 4> = (0..3).map(|_| rc.clone()).collect();
+        assert_eq!(Rc::strong_count(&rc), 4);
+        drop(l);
+        assert_eq!(Rc::strong_count(&rc), 1);
+
+        let mut l: SmallSeqList<_, 4> = (0..6).map(|_| rc.clone()).collect();
+        l.pop();
+        assert_eq!(Rc::strong_count(&rc), 6);
+        drop(l);
+        assert_eq!(Rc::strong_count(&rc), 1);
+    }
+
+    #[test]
+    fn test_partition() {
+        super::super::test::test_partition::<SmallSeqList<i32, 8>>();
+    }
+}
@@ -0,0 +1,246 @@
+//! A binary format for lists of plain data, which loads without parsing.
+//!
+//! A snapshot is a header of `HEADER_LEN` bytes followed by the elements as
+//! they are laid out in memory. The header holds, in order:
+//! - the magic bytes `DSSL`,
+//! - the format version as a little-endian `u16`,
+//! - the byte order of the elements, 0 for little and 1 for big endian,
+//! - one reserved byte,
+//! - the size of an element as a little-endian `u32`,
+//! - four reserved bytes,
+//! - the number of elements as a little-endian `u64`,
+//! - and eight reserved bytes.
+//!
+//! The reserved bytes are zero. Since the header is 32 bytes long, the
+//! elements of a snapshot in a page-aligned buffer such as a memory-mapped
+//! file are aligned too, so [`view`] can borrow them in place.
+
+use alloc::vec::Vec;
+use core::{fmt, mem, ptr};
+
+use allocator_api2::alloc::Allocator;
+use bytemuck::Pod;
+
+use crate::seq_list::SeqList;
+
+pub const HEADER_LEN: usize = 32;
+pub const VERSION: u16 = 1;
+const MAGIC: [u8; 4] = *b"DSSL";
+const NATIVE_ENDIAN: u8 = if cfg!(target_endian = "little") { 0 } else { 1 };
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SnapshotError {
+    TooShort,
+    BadMagic,
+    UnsupportedVersion(u16),
+    /// The elements were written on a machine of the other byte order.
+    ForeignEndianness,
+    ElementSize {
+        expected: usize,
+        found: u64,
+    },
+    /// The number of bytes after the header does not match the length.
+    Length {
+        expected: u64,
+        found: usize,
+    },
+    Misaligned,
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::TooShort => write!(f, "the snapshot is shorter than its header"),
+            SnapshotError::BadMagic => write!(f, "not a snapshot"),
+            SnapshotError::UnsupportedVersion(v) => write!(f, "unsupported version {}", v),
+            SnapshotError::ForeignEndianness => write!(f, "written with the other byte order"),
+            SnapshotError::ElementSize { expected, found } => {
+                write!(f, "elements of {} bytes, expected {}", found, expected)
+            }
+            SnapshotError::Length { expected, found } => {
+                write!(f, "{} bytes of elements, expected {}", found, expected)
+            }
+            SnapshotError::Misaligned => write!(f, "the elements are misaligned"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SnapshotError {}
+
+fn header<T>(len: usize) -> [u8; HEADER_LEN] {
+    let mut header = [0; HEADER_LEN];
+    header[0..4].copy_from_slice(&MAGIC);
+    header[4..6].copy_from_slice(&VERSION.to_le_bytes());
+    header[6] = NATIVE_ENDIAN;
+    header[8..12].copy_from_slice(&(mem::size_of::<T>() as u32).to_le_bytes());
+    header[16..24].copy_from_slice(&(len as u64).to_le_bytes());
+    header
+}
+
+/// Checks the header and returns the number of elements it promises.
+fn parse_header<T>(header: &[u8]) -> Result<u64, SnapshotError> {
+    if header.len() < HEADER_LEN {
+        return Err(SnapshotError::TooShort);
+    }
+    if header[0..4] != MAGIC {
+        return Err(SnapshotError::BadMagic);
+    }
+    let version = u16::from_le_bytes([header[4], header[5]]);
+    if version != VERSION {
+        return Err(SnapshotError::UnsupportedVersion(version));
+    }
+    if header[6] != NATIVE_ENDIAN {
+        return Err(SnapshotError::ForeignEndianness);
+    }
+    let size = u32::from_le_bytes(header[8..12].try_into().unwrap()) as u64;
+    if size != mem::size_of::<T>() as u64 {
+        return Err(SnapshotError::ElementSize {
+            expected: mem::size_of::<T>(),
+            found: size,
+        });
+    }
+    Ok(u64::from_le_bytes(header[16..24].try_into().unwrap()))
+}
+
+/// Checks the whole snapshot and returns the number of elements and their
+/// bytes.
+fn body<T>(bytes: &[u8]) -> Result<(usize, &[u8]), SnapshotError> {
+    let len = parse_header::<T>(bytes)?;
+    let body = &bytes[HEADER_LEN..];
+    let expected = len.checked_mul(mem::size_of::<T>() as u64);
+    if expected != Some(body.len() as u64) {
+        return Err(SnapshotError::Length {
+            expected: expected.unwrap_or(u64::MAX),
+            found: body.len(),
+        });
+    }
+    Ok((len as usize, body))
+}
+
+/// Borrows the elements of a snapshot without copying them.
+pub fn view<T: Pod>(bytes: &[u8]) -> Result<&[T], SnapshotError> {
+    let (_, body) = body::<T>(bytes)?;
+    bytemuck::try_cast_slice(body).map_err(|_| SnapshotError::Misaligned)
+}
+
+impl<T: Pod, A: Allocator> SeqList<T, A> {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_LEN + mem::size_of_val::<[T]>(self));
+        bytes.extend_from_slice(&header::<T>(self.len()));
+        bytes.extend_from_slice(bytemuck::cast_slice(self));
+        bytes
+    }
+
+    #[cfg(feature = "std")]
+    pub fn write_to<W: std::io::Write>(&self, mut writer: W) -> std::io::Result<()> {
+        writer.write_all(&header::<T>(self.len()))?;
+        writer.write_all(bytemuck::cast_slice(self))
+    }
+}
+
+impl<T: Pod, A: Allocator + Default> SeqList<T, A> {
+    /// Copies the elements out of a snapshot, which need not be aligned.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SnapshotError> {
+        let (len, body) = body::<T>(bytes)?;
+        let mut l = Self::default();
+        l.reserve(len);
+        unsafe {
+            ptr::copy_nonoverlapping(body.as_ptr(), l.as_mut_ptr().cast(), body.len());
+            l.set_len(len);
+        }
+        Ok(l)
+    }
+
+    /// Reads a snapshot written by `write_to`. A malformed header is
+    /// reported as `InvalidData`.
+    #[cfg(feature = "std")]
+    pub fn read_from<R: std::io::Read>(mut reader: R) -> std::io::Result<Self> {
+        use std::io::{Error, ErrorKind};
+
+        let mut header = [0; HEADER_LEN];
+        reader.read_exact(&mut header)?;
+        let len = parse_header::<T>(&header).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        let mut remaining =
+            usize::try_from(len).map_err(|_| Error::from(ErrorKind::OutOfMemory))?;
+        // Grow as the elements arrive rather than trusting the header.
+        let mut l = Self::default();
+        let mut chunk = [T::zeroed(); 1024];
+        while remaining > 0 {
+            let n = remaining.min(chunk.len());
+            reader.read_exact(bytemuck::cast_slice_mut(&mut chunk[..n]))?;
+            l.reserve(n);
+            for &elem in &chunk[..n] {
+                l.push(elem);
+            }
+            remaining -= n;
+        }
+        Ok(l)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test() {
+        let l: SeqList<i32> = (-5..100).collect();
+        let bytes = l.to_bytes();
+        assert_eq!(bytes.len(), HEADER_LEN + 105 * 4);
+        assert_eq!(&bytes[..4], b"DSSL");
+
+        let loaded = SeqList::<i32>::from_bytes(&bytes).unwrap();
+        assert_eq!(*loaded, *l);
+
+        let mut written = Vec::new();
+        l.write_to(&mut written).unwrap();
+        assert_eq!(written, bytes);
+        let read = SeqList::<i32>::read_from(&written[..]).unwrap();
+        assert_eq!(*read, *l);
+
+        // A `u64` buffer keeps the elements aligned.
+        let mut aligned = vec![0_u64; bytes.len() / 8 + 1];
+        bytemuck::cast_slice_mut::<u64, u8>(&mut aligned)[..bytes.len()].copy_from_slice(&bytes);
+        let aligned = &bytemuck::cast_slice::<u64, u8>(&aligned)[..bytes.len()];
+        assert_eq!(view::<i32>(aligned).unwrap(), &*l);
+        assert_eq!(view::<i32>(&aligned[1..]), Err(SnapshotError::BadMagic));
+
+        let empty = SeqList::<f64>::new().to_bytes();
+        assert!(SeqList::<f64>::from_bytes(&empty).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_validation() {
+        let bytes = (0..10).collect::<SeqList<u32>>().to_bytes();
+        let load = |bytes: &[u8]| SeqList::<u32>::from_bytes(bytes).map(|_| ());
+
+        assert_eq!(load(&bytes[..HEADER_LEN - 1]), Err(SnapshotError::TooShort));
+        assert_eq!(
+            load(&bytes[..bytes.len() - 1]),
+            Err(SnapshotError::Length {
+                expected: 40,
+                found: 39
+            })
+        );
+        assert_eq!(
+            SeqList::<u16>::from_bytes(&bytes).map(|_| ()),
+            Err(SnapshotError::ElementSize {
+                expected: 2,
+                found: 4
+            })
+        );
+
+        let mut corrupt = bytes.clone();
+        corrupt[0] = b'X';
+        assert_eq!(load(&corrupt), Err(SnapshotError::BadMagic));
+        let mut corrupt = bytes.clone();
+        corrupt[4] = 2;
+        assert_eq!(load(&corrupt), Err(SnapshotError::UnsupportedVersion(2)));
+        let mut corrupt = bytes.clone();
+        corrupt[6] ^= 1;
+        assert_eq!(load(&corrupt), Err(SnapshotError::ForeignEndianness));
+
+        assert!(SeqList::<u32>::read_from(&bytes[..bytes.len() - 1]).is_err());
+    }
+}
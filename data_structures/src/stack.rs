@@ -1,19 +1,114 @@
-use crate::{cir_linked_list::CirLinkedList, seq_list::SeqList, List};
+#[cfg(feature = "alloc")]
+use core::{marker::PhantomData, ops::DerefMut};
 
-pub trait Stack<T> {
+use crate::array_list::ArrayList;
+#[cfg(feature = "alloc")]
+use crate::{cir_linked_list::CirLinkedList, seq_list::SeqList, small_seq_list::SmallSeqList};
+
+/// A LIFO container. Iterating over a stack yields its elements from the
+/// bottom to the top.
+pub trait Stack<T>
+where
+    for<'a> &'a Self: IntoIterator<Item = &'a T>,
+{
+    fn new() -> Self;
+
+    fn with_capacity(_capacity: usize) -> Self
+    where
+        Self: Sized,
+    {
+        Self::new()
+    }
+
+    fn push(&mut self, elem: T);
+    fn pop(&mut self) -> Option<T>;
+    fn peek(&self) -> Option<&T>;
+    fn peek_mut(&mut self) -> Option<&mut T>;
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn clear(&mut self) {
+        while self.pop().is_some() {}
+    }
+}
+
+/// The contiguous storage behind a `SeqStack`.
+#[cfg(feature = "alloc")]
+pub trait StackStorage<T>: DerefMut<Target = [T]> {
     fn new() -> Self;
+    fn with_capacity(capacity: usize) -> Self;
     fn push(&mut self, elem: T);
     fn pop(&mut self) -> Option<T>;
 }
 
-pub struct SeqStack<T> {
-    list: SeqList<T>,
+#[cfg(feature = "alloc")]
+impl<T> StackStorage<T> for SeqList<T> {
+    fn new() -> Self {
+        SeqList::new()
+    }
+
+    fn with_capacity(capacity: usize) -> Self {
+        SeqList::with_capacity(capacity)
+    }
+
+    #[inline]
+    fn push(&mut self, elem: T) {
+        SeqList::push(self, elem)
+    }
+
+    #[inline]
+    fn pop(&mut self) -> Option<T> {
+        SeqList::pop(self)
+    }
 }
 
-impl<T> Stack<T> for SeqStack<T> {
+#[cfg(feature = "alloc")]
+impl<T, const N: usize> StackStorage<T> for SmallSeqList<T, N> {
     fn new() -> Self {
+        SmallSeqList::new()
+    }
+
+    fn with_capacity(capacity: usize) -> Self {
+        SmallSeqList::with_capacity(capacity)
+    }
+
+    #[inline]
+    fn push(&mut self, elem: T) {
+        SmallSeqList::push(self, elem)
+    }
+
+    #[inline]
+    fn pop(&mut self) -> Option<T> {
+        SmallSeqList::pop(self)
+    }
+}
+
+#[cfg(feature = "alloc")]
+pub struct SeqStack<T, L = SeqList<T>> {
+    list: L,
+    marker: PhantomData<T>,
+}
+
+/// A stack that keeps up to `N` elements without allocating.
+#[cfg(feature = "alloc")]
+pub type SmallStack<T, const N: usize> = SeqStack<T, SmallSeqList<T, N>>;
+
+#[cfg(feature = "alloc")]
+impl<T, L: StackStorage<T>> Stack<T> for SeqStack<T, L> {
+    fn new() -> Self {
+        SeqStack {
+            list: L::new(),
+            marker: PhantomData,
+        }
+    }
+
+    fn with_capacity(capacity: usize) -> Self {
         SeqStack {
-            list: SeqList::new(),
+            list: L::with_capacity(capacity),
+            marker: PhantomData,
         }
     }
 
@@ -26,40 +121,417 @@ impl<T> Stack<T> for SeqStack<T> {
     fn pop(&mut self) -> Option<T> {
         self.list.pop()
     }
-}
 
-impl<T> SeqStack<T> {
-    pub fn peek(&self) -> Option<&T> {
+    #[inline]
+    fn peek(&self) -> Option<&T> {
         self.list.last()
     }
 
-    pub fn peek_mut(&mut self) -> Option<&mut T> {
+    #[inline]
+    fn peek_mut(&mut self) -> Option<&mut T> {
         self.list.last_mut()
     }
 
-    pub fn list(self) -> SeqList<T> {
+    #[inline]
+    fn len(&self) -> usize {
+        self.list.len()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T, L> SeqStack<T, L> {
+    pub fn list(self) -> L {
         self.list
     }
 }
 
-struct LinkedStack<T> {
+#[cfg(feature = "alloc")]
+impl<T, L: StackStorage<T>> Default for SeqStack<T, L> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'a, T, L: StackStorage<T>> IntoIterator for &'a SeqStack<T, L> {
+    type Item = &'a T;
+
+    type IntoIter = core::slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.list.iter()
+    }
+}
+
+#[cfg(feature = "alloc")]
+pub struct LinkedStack<T> {
     list: CirLinkedList<T>,
+    len: usize,
 }
 
+#[cfg(feature = "alloc")]
 impl<T> Stack<T> for LinkedStack<T> {
     fn new() -> Self {
         LinkedStack {
             list: CirLinkedList::new(),
+            len: 0,
         }
     }
 
     #[inline]
     fn push(&mut self, elem: T) {
         self.list.push(elem);
+        self.len += 1;
+    }
+
+    #[inline]
+    fn pop(&mut self) -> Option<T> {
+        let elem = self.list.pop()?;
+        self.len -= 1;
+        Some(elem)
+    }
+
+    #[inline]
+    fn peek(&self) -> Option<&T> {
+        self.list.last()
+    }
+
+    #[inline]
+    fn peek_mut(&mut self) -> Option<&mut T> {
+        self.list.last_mut()
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn clear(&mut self) {
+        self.list = CirLinkedList::new();
+        self.len = 0;
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T> Default for LinkedStack<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'a, T> IntoIterator for &'a LinkedStack<T> {
+    type Item = &'a T;
+
+    type IntoIter = crate::cir_linked_list::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.list.iter()
+    }
+}
+
+#[cfg(feature = "alloc")]
+struct MinMaxEntry<T> {
+    elem: T,
+    // Indices of the least and the greatest elements below this one.
+    min_below: usize,
+    max_below: usize,
+}
+
+/// A stack that also answers `min` and `max` in O(1).
+#[cfg(feature = "alloc")]
+pub struct MinMaxStack<T> {
+    stack: SeqStack<MinMaxEntry<T>>,
+}
+
+#[cfg(feature = "alloc")]
+impl<T: Ord> MinMaxStack<T> {
+    pub fn min(&self) -> Option<&T> {
+        let top = self.stack.len().checked_sub(1)?;
+        Some(&self.stack.list[self.min_index(top)].elem)
+    }
+
+    pub fn max(&self) -> Option<&T> {
+        let top = self.stack.len().checked_sub(1)?;
+        Some(&self.stack.list[self.max_index(top)].elem)
+    }
+
+    // Elements below the top never change, so only the top has to be
+    // compared again here. This keeps `peek_mut` safe to use.
+    fn min_index(&self, top: usize) -> usize {
+        let list = &self.stack.list;
+        let below = list[top].min_below;
+        if top == 0 || list[top].elem < list[below].elem {
+            top
+        } else {
+            below
+        }
+    }
+
+    fn max_index(&self, top: usize) -> usize {
+        let list = &self.stack.list;
+        let below = list[top].max_below;
+        if top == 0 || list[top].elem > list[below].elem {
+            top
+        } else {
+            below
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: Ord> Stack<T> for MinMaxStack<T> {
+    fn new() -> Self {
+        MinMaxStack {
+            stack: SeqStack::new(),
+        }
+    }
+
+    fn with_capacity(capacity: usize) -> Self {
+        MinMaxStack {
+            stack: SeqStack::with_capacity(capacity),
+        }
+    }
+
+    fn push(&mut self, elem: T) {
+        let (min_below, max_below) = match self.stack.len().checked_sub(1) {
+            Some(top) => (self.min_index(top), self.max_index(top)),
+            None => (0, 0),
+        };
+        self.stack.push(MinMaxEntry {
+            elem,
+            min_below,
+            max_below,
+        })
+    }
+
+    #[inline]
+    fn pop(&mut self) -> Option<T> {
+        self.stack.pop().map(|entry| entry.elem)
+    }
+
+    #[inline]
+    fn peek(&self) -> Option<&T> {
+        self.stack.peek().map(|entry| &entry.elem)
+    }
+
+    #[inline]
+    fn peek_mut(&mut self) -> Option<&mut T> {
+        self.stack.peek_mut().map(|entry| &mut entry.elem)
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        self.stack.len()
+    }
+
+    fn clear(&mut self) {
+        self.stack = SeqStack::new();
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: Ord> Default for MinMaxStack<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "alloc")]
+pub struct MinMaxIter<'a, T> {
+    iter: core::slice::Iter<'a, MinMaxEntry<T>>,
+}
+
+#[cfg(feature = "alloc")]
+impl<'a, T> Iterator for MinMaxIter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|entry| &entry.elem)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'a, T> IntoIterator for &'a MinMaxStack<T> {
+    type Item = &'a T;
+
+    type IntoIter = MinMaxIter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        MinMaxIter {
+            iter: self.stack.list.iter(),
+        }
+    }
+}
+
+/// A stack of at most `N` elements that never allocates.
+pub struct ArrayStack<T, const N: usize> {
+    list: ArrayList<T, N>,
+}
+
+impl<T, const N: usize> ArrayStack<T, N> {
+    /// Gives `elem` back if the stack is full.
+    pub fn try_push(&mut self, elem: T) -> Result<(), T> {
+        self.list.try_push(elem)
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.list.is_full()
+    }
+}
+
+impl<T, const N: usize> Stack<T> for ArrayStack<T, N> {
+    fn new() -> Self {
+        ArrayStack {
+            list: ArrayList::new(),
+        }
+    }
+
+    /// # Panics
+    /// Panics if the stack is full.
+    #[inline]
+    fn push(&mut self, elem: T) {
+        self.list.push(elem)
     }
 
     #[inline]
     fn pop(&mut self) -> Option<T> {
         self.list.pop()
     }
+
+    #[inline]
+    fn peek(&self) -> Option<&T> {
+        self.list.last()
+    }
+
+    #[inline]
+    fn peek_mut(&mut self) -> Option<&mut T> {
+        self.list.last_mut()
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    fn clear(&mut self) {
+        self.list.clear()
+    }
+}
+
+impl<T, const N: usize> Default for ArrayStack<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a ArrayStack<T, N> {
+    type Item = &'a T;
+
+    type IntoIter = core::slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.list.iter()
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+    #[cfg(feature = "alloc")]
+    use rand::Rng;
+
+    pub fn test_stack<S: Stack<i32>>()
+    where
+        for<'a> &'a S: IntoIterator<Item = &'a i32>,
+    {
+        let mut s = S::with_capacity(4);
+        assert!(s.is_empty());
+        assert!(s.peek().is_none());
+        for i in 1..=100 {
+            s.push(i);
+            assert_eq!(s.peek(), Some(&i));
+        }
+        assert_eq!(s.len(), 100);
+        assert!(s.into_iter().eq((1..=100).collect::<Vec<_>>().iter()));
+
+        *s.peek_mut().unwrap() = 1000;
+        assert_eq!(s.pop(), Some(1000));
+        assert_eq!(s.pop(), Some(99));
+        assert_eq!(s.len(), 98);
+
+        s.clear();
+        assert!(s.is_empty());
+        assert!(s.pop().is_none());
+        s.push(1);
+        assert_eq!(s.pop(), Some(1));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_seq_stack() {
+        test_stack::<SeqStack<i32>>();
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_small_stack() {
+        test_stack::<SmallStack<i32, 8>>();
+
+        let mut s = SmallStack::<i32, 4>::new();
+        for i in 0..4 {
+            s.push(i);
+        }
+        assert!(!s.list.spilled());
+        s.push(4);
+        assert!(s.list().spilled());
+    }
+
+    #[test]
+    fn test_array_stack() {
+        test_stack::<ArrayStack<i32, 100>>();
+
+        let mut s = ArrayStack::<i32, 2>::new();
+        assert_eq!(s.try_push(1), Ok(()));
+        assert_eq!(s.try_push(2), Ok(()));
+        assert!(s.is_full());
+        assert_eq!(s.try_push(3), Err(3));
+        assert_eq!(s.pop(), Some(2));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_linked_stack() {
+        test_stack::<LinkedStack<i32>>();
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_min_max_stack() {
+        test_stack::<MinMaxStack<i32>>();
+
+        let mut rng = rand::thread_rng();
+        let mut s = MinMaxStack::new();
+        let mut model = Vec::new();
+        assert!(s.min().is_none());
+        for _ in 0..1000 {
+            match rng.gen_range(0..4) {
+                0 | 1 => {
+                    let i: i32 = rng.gen_range(-100..100);
+                    s.push(i);
+                    model.push(i);
+                }
+                2 => assert_eq!(s.pop(), model.pop()),
+                _ => {
+                    let i: i32 = rng.gen_range(-100..100);
+                    if let Some(top) = s.peek_mut() {
+                        *top = i;
+                        *model.last_mut().unwrap() = i;
+                    }
+                }
+            }
+            assert_eq!(s.min(), model.iter().min());
+            assert_eq!(s.max(), model.iter().max());
+        }
+    }
 }
@@ -0,0 +1,437 @@
+//! The expression tree of a postfix expression, which `eval` evaluates and
+//! can show along with what it simplifies to.
+
+use std::fmt::{self, Display, Write};
+
+use data_structures::stack::{SeqStack, Stack};
+
+use super::{
+    functions::Builtin,
+    number::{self, Number},
+    Associativity, EvalError, Operator, Token,
+};
+
+/// The height of the tallest tree, which bounds the recursion into it. A
+/// chain like `1 + 2 + 3` is as tall as it is long, so this is well above
+/// what anyone types.
+pub const MAX_HEIGHT: usize = 10_000;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Expr<N> {
+    Num(N),
+    /// A name and where it is.
+    Variable(String, usize),
+    /// An operator, its operand and where the operator is.
+    Unary(Operator, Box<Expr<N>>, usize),
+    /// An operator, its operands and where the operator is.
+    Binary(Operator, Box<Expr<N>>, Box<Expr<N>>, usize),
+    /// `a ? b : c`.
+    Conditional(Box<Expr<N>>, Box<Expr<N>>, Box<Expr<N>>),
+    /// The name of a function, the arguments and where it is.
+    Call(String, Vec<Expr<N>>, usize),
+}
+
+impl<N: Number> Expr<N> {
+    /// Builds the tree the way `eval_postfix` evaluates the tokens, with
+    /// subtrees in place of values.
+    pub fn from_postfix(postfix: &[Token<N>]) -> Result<Self, EvalError<N>> {
+        // Each subtree with where it starts and its height.
+        let mut stack = SeqStack::<(Expr<N>, usize, usize)>::new();
+        let pop = |stack: &mut SeqStack<_>, pos| stack.pop().ok_or(EvalError::MissingOperand(pos));
+        for token in postfix {
+            let (expr, start, height, pos) = match token {
+                Token::Num(n, pos) => (Expr::Num(n.clone()), *pos, 1, *pos),
+                Token::Variable(name, pos) => (Expr::Variable(name.clone(), *pos), *pos, 1, *pos),
+                Token::Operator(Operator::Conditional, pos) => {
+                    let (operand2, _, height2) = pop(&mut stack, *pos)?;
+                    let (operand1, _, height1) = pop(&mut stack, *pos)?;
+                    let (condition, start, height) = pop(&mut stack, *pos)?;
+                    let expr = Expr::Conditional(
+                        Box::new(condition),
+                        Box::new(operand1),
+                        Box::new(operand2),
+                    );
+                    (expr, start, 1 + height.max(height1).max(height2), *pos)
+                }
+                Token::Operator(op, pos) if op.is_unary() => {
+                    let (operand, _, height) = pop(&mut stack, *pos)?;
+                    let expr = Expr::Unary(op.clone(), Box::new(operand), *pos);
+                    (expr, *pos, 1 + height, *pos)
+                }
+                Token::Operator(op, pos) => {
+                    let (operand2, _, height2) = pop(&mut stack, *pos)?;
+                    let (operand1, start, height1) = pop(&mut stack, *pos)?;
+                    let expr =
+                        Expr::Binary(op.clone(), Box::new(operand1), Box::new(operand2), *pos);
+                    (expr, start, 1 + height1.max(height2), *pos)
+                }
+                Token::Call(name, count, pos) => {
+                    let mut args = Vec::with_capacity(*count);
+                    let mut height = 0;
+                    for _ in 0..*count {
+                        let (arg, _, arg_height) = pop(&mut stack, *pos)?;
+                        args.push(arg);
+                        height = height.max(arg_height);
+                    }
+                    args.reverse();
+                    (Expr::Call(name.clone(), args, *pos), *pos, 1 + height, *pos)
+                }
+            };
+            // Evaluating, showing and dropping a tree recurse into it.
+            if height > MAX_HEIGHT {
+                return Err(EvalError::NestingLimit(pos));
+            }
+            stack.push((expr, start, height));
+        }
+        // The operand after the first one has no operator to take it.
+        let (mut expr, mut start, _) = stack.pop().ok_or(EvalError::MissingOperand(0))?;
+        let mut extra = None;
+        while let Some((below, below_start, _)) = stack.pop() {
+            extra = Some(start);
+            (expr, start) = (below, below_start);
+        }
+        match extra {
+            Some(pos) => Err(EvalError::TooManyOperands(pos)),
+            None => Ok(expr),
+        }
+    }
+
+    /// Evaluates the operators and built-in calls whose operands are all
+    /// numbers, and the logical operators and conditionals decided by their
+    /// first operand. Those that fail are kept, so that evaluating the tree
+    /// reports the error.
+    pub fn fold(self) -> Self {
+        match self {
+            Expr::Unary(op, operand, pos) => match operand.fold() {
+                Expr::Num(n) if N::supports(&op) => match number::apply_unary(&op, &n) {
+                    Some(result) => Expr::Num(result),
+                    None => Expr::Unary(op, Box::new(Expr::Num(n)), pos),
+                },
+                operand => Expr::Unary(op, Box::new(operand), pos),
+            },
+            Expr::Binary(op, operand1, operand2, pos) => match (operand1.fold(), operand2.fold()) {
+                (Expr::Num(a), _)
+                    if matches!(op, Operator::And | Operator::Or)
+                        && a.is_true() == (op == Operator::Or) =>
+                {
+                    Expr::Num(N::from_bool(a.is_true()))
+                }
+                (Expr::Num(a), Expr::Num(b)) if N::supports(&op) => {
+                    match number::apply(&op, &a, &b) {
+                        Some(result) => Expr::Num(result),
+                        None => {
+                            Expr::Binary(op, Box::new(Expr::Num(a)), Box::new(Expr::Num(b)), pos)
+                        }
+                    }
+                }
+                (operand1, operand2) => {
+                    Expr::Binary(op, Box::new(operand1), Box::new(operand2), pos)
+                }
+            },
+            Expr::Conditional(condition, operand1, operand2) => match condition.fold() {
+                Expr::Num(n) if n.is_true() => operand1.fold(),
+                Expr::Num(_) => operand2.fold(),
+                condition => Expr::Conditional(
+                    Box::new(condition),
+                    Box::new(operand1.fold()),
+                    Box::new(operand2.fold()),
+                ),
+            },
+            Expr::Call(name, args, pos) => {
+                let args: Vec<_> = args.into_iter().map(Expr::fold).collect();
+                let numbers: Option<Vec<N>> = args
+                    .iter()
+                    .map(|arg| match arg {
+                        Expr::Num(n) => Some(n.clone()),
+                        _ => None,
+                    })
+                    .collect();
+                let result = Builtin::from_name(&name)
+                    .filter(|builtin| N::supports_builtin(builtin))
+                    .filter(|builtin| builtin.arity().accepts(args.len()))
+                    .zip(numbers)
+                    .and_then(|(builtin, numbers)| builtin.call(&numbers));
+                match result {
+                    Some(result) => Expr::Num(result),
+                    None => Expr::Call(name, args, pos),
+                }
+            }
+            expr => expr,
+        }
+    }
+
+    /// Removes the operations that leave their operand unchanged, like
+    /// `x * 1`, `x + 0` or `+x`.
+    pub fn simplify(self) -> Self {
+        let is = |expr: &Expr<N>, k| matches!(expr, Expr::Num(n) if *n == N::from_i32(k));
+        match self {
+            Expr::Unary(Operator::Plus, operand, _) => operand.simplify(),
+            Expr::Unary(op, operand, pos) => Expr::Unary(op, Box::new(operand.simplify()), pos),
+            Expr::Binary(op, operand1, operand2, pos) => {
+                let (a, b) = (operand1.simplify(), operand2.simplify());
+                match op {
+                    Operator::Add if is(&b, 0) => a,
+                    Operator::Add if is(&a, 0) => b,
+                    Operator::Subtract if is(&b, 0) => a,
+                    Operator::Subtract if is(&a, 0) => {
+                        Expr::Unary(Operator::Negate, Box::new(b), pos)
+                    }
+                    Operator::Multiply if is(&b, 1) => a,
+                    Operator::Multiply if is(&a, 1) => b,
+                    Operator::Divide | Operator::Power if is(&b, 1) => a,
+                    op => Expr::Binary(op, Box::new(a), Box::new(b), pos),
+                }
+            }
+            Expr::Conditional(condition, operand1, operand2) => Expr::Conditional(
+                Box::new(condition.simplify()),
+                Box::new(operand1.simplify()),
+                Box::new(operand2.simplify()),
+            ),
+            Expr::Call(name, args, pos) => {
+                Expr::Call(name, args.into_iter().map(Expr::simplify).collect(), pos)
+            }
+            expr => expr,
+        }
+    }
+
+    /// Whether the value is a truth value, to be shown as `true` or `false`.
+    pub fn is_boolean(&self) -> bool {
+        match self {
+            Expr::Unary(op, _, _) | Expr::Binary(op, _, _, _) => op.is_logical(),
+            Expr::Conditional(_, operand1, operand2) => {
+                operand1.is_boolean() && operand2.is_boolean()
+            }
+            _ => false,
+        }
+    }
+
+    /// How tightly the expression holds together when written out, on the
+    /// scale of `Operator::precedence`.
+    fn precedence(&self) -> u32 {
+        match self {
+            Expr::Unary(op, _, _) | Expr::Binary(op, _, _, _) => op.precedence(),
+            Expr::Conditional(..) => Operator::Conditional.precedence(),
+            // Written out, a fraction is a division and a negative number a
+            // negation.
+            Expr::Num(n) => {
+                let n = n.to_string();
+                if n.contains('/') {
+                    Operator::Divide.precedence()
+                } else if n.starts_with('-') {
+                    Operator::Negate.precedence()
+                } else {
+                    u32::MAX
+                }
+            }
+            Expr::Variable(..) | Expr::Call(..) => u32::MAX,
+        }
+    }
+
+    /// Draws the tree with one node per line.
+    pub fn tree(&self) -> String {
+        let mut s = String::new();
+        self.write_tree(&mut s, "", "").unwrap();
+        s
+    }
+
+    fn write_tree(&self, s: &mut String, first: &str, rest: &str) -> fmt::Result {
+        let children: Vec<&Expr<N>> = match self {
+            Expr::Num(n) => return writeln!(s, "{}{}", first, n),
+            Expr::Variable(name, _) => return writeln!(s, "{}{}", first, name),
+            Expr::Unary(op, operand, _) => {
+                writeln!(s, "{}{}", first, op)?;
+                vec![operand]
+            }
+            Expr::Binary(op, operand1, operand2, _) => {
+                writeln!(s, "{}{}", first, op)?;
+                vec![operand1, operand2]
+            }
+            Expr::Conditional(condition, operand1, operand2) => {
+                writeln!(s, "{}{}", first, Operator::Conditional)?;
+                vec![condition, operand1, operand2]
+            }
+            Expr::Call(name, args, _) => {
+                writeln!(s, "{}{}()", first, name)?;
+                args.iter().collect()
+            }
+        };
+        for (i, child) in children.iter().enumerate() {
+            if i + 1 == children.len() {
+                child.write_tree(s, &format!("{}└─ ", rest), &format!("{}   ", rest))?;
+            } else {
+                child.write_tree(s, &format!("{}├─ ", rest), &format!("{}│  ", rest))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn write_operand<N: Number>(
+    f: &mut fmt::Formatter<'_>,
+    operand: &Expr<N>,
+    parenthesised: bool,
+) -> fmt::Result {
+    if parenthesised {
+        write!(f, "({})", operand)
+    } else {
+        write!(f, "{}", operand)
+    }
+}
+
+/// Writes the expression in infix with only the parentheses it needs.
+impl<N: Number> Display for Expr<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Num(n) => write!(f, "{}", n),
+            Expr::Variable(name, _) => write!(f, "{}", name),
+            Expr::Unary(op, operand, _) => {
+                write!(f, "{}", op)?;
+                write_operand(f, operand, operand.precedence() < op.precedence())
+            }
+            Expr::Binary(op, operand1, operand2, _) => {
+                let (p, p1, p2) = (
+                    op.precedence(),
+                    operand1.precedence(),
+                    operand2.precedence(),
+                );
+                let associativity = op.associativity();
+                write_operand(
+                    f,
+                    operand1,
+                    p1 < p || p1 == p && associativity == Associativity::Right,
+                )?;
+                write!(f, " {} ", op)?;
+                // A prefix operator takes everything that binds tighter than
+                // itself, so it needs no parentheses on the right.
+                let prefix = p2 == Operator::Negate.precedence();
+                write_operand(
+                    f,
+                    operand2,
+                    !prefix && (p2 < p || p2 == p && associativity == Associativity::Left),
+                )
+            }
+            // `?` and `:` delimit the middle operand, and conditionals nest to
+            // the right.
+            Expr::Conditional(condition, operand1, operand2) => {
+                write_operand(
+                    f,
+                    condition,
+                    condition.precedence() <= Operator::Conditional.precedence(),
+                )?;
+                write!(f, " ? {} : {}", operand1, operand2)
+            }
+            Expr::Call(name, args, _) => {
+                write!(f, "{}(", name)?;
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", arg)?;
+                }
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use num_rational::BigRational;
+
+    use super::super::infix_to_postfix;
+    use super::*;
+
+    fn parse<N: Number>(expression: &str) -> Expr<N> {
+        Expr::from_postfix(&infix_to_postfix(expression).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_display() {
+        for expression in [
+            "1 + 2 * 3",
+            "(1 + 2) * 3",
+            "1 - (2 - 3)",
+            "1 - 2 - 3",
+            "2 ** 3 ** 2",
+            "(2 ** 3) ** 2",
+            "-2 ** 2",
+            "(-2) ** 2",
+            "2 ** -x * 3",
+            "--x",
+            "1 - -2",
+            "max(a, b + 1, f()) % 3",
+            "1 | 2 ^ 3 & 4 << 5 + 6 // 7",
+            "a ? b : c ? d : e",
+            "(a ? b : c) ? d : e",
+            "a ? b ? c : d : e",
+            "(a ? b : c) + 1",
+            "!(a && b) || c == -d < e",
+        ] {
+            assert_eq!(parse::<i32>(expression).to_string(), expression);
+        }
+        assert_eq!(parse::<i32>("((1)) + ((2 * (x)))").to_string(), "1 + 2 * x");
+        let postfix = [Token::Num(1, 0), Token::Operator(Operator::Add, 2)];
+        assert!(Expr::<i32>::from_postfix(&postfix).is_err());
+    }
+
+    #[test]
+    fn test_fold() {
+        assert_eq!(parse::<i32>("1 + 2 * 3").fold(), Expr::Num(7));
+        assert_eq!(
+            parse::<i32>("x * (2 + 3) - abs(-4)").fold().to_string(),
+            "x * 5 - 4"
+        );
+        // Errors are left for the evaluation.
+        assert_eq!(parse::<i32>("x + 1 / 0").fold().to_string(), "x + 1 / 0");
+        assert_eq!(parse::<f64>("sqrt(4) + x").fold().to_string(), "2 + x");
+
+        // A folded negative or fractional number keeps its meaning.
+        assert_eq!(parse::<i32>("(0 - 2) ** 2 + x").fold().to_string(), "4 + x");
+        assert_eq!(parse::<i32>("x ** (0 - 2)").fold().to_string(), "x ** -2");
+        assert_eq!(parse::<i32>("(0 - 2) ** x").fold().to_string(), "(-2) ** x");
+        assert_eq!(
+            parse::<BigRational>("x ** (1 / 2) + (1 / 2) ** x")
+                .fold()
+                .to_string(),
+            "x ** (1/2) + (1/2) ** x"
+        );
+    }
+
+    #[test]
+    fn test_fold_logic() {
+        let fold = |expression| parse::<i32>(expression).fold().to_string();
+        assert_eq!(fold("x < 1 + 1"), "x < 2");
+        assert_eq!(fold("0 && x || 3 > 2"), "1");
+        assert_eq!(fold("1 && x"), "1 && x");
+        assert_eq!(fold("1 ? x : 1 / 0"), "x");
+        assert_eq!(fold("x ? 2 > 1 : !0"), "x ? 1 : 1");
+        assert!(parse::<i32>("x ? a < b : !c").is_boolean());
+        assert!(!parse::<i32>("x ? a < b : c").is_boolean());
+    }
+
+    #[test]
+    fn test_simplify() {
+        let simplify = |expression| parse::<i32>(expression).fold().simplify().to_string();
+        assert_eq!(simplify("x * 1 + 0"), "x");
+        assert_eq!(simplify("1 * (0 + x) / 1 ** y"), "x / 1 ** y");
+        assert_eq!(simplify("(2 - 1) * x ** (3 - 2)"), "x");
+        assert_eq!(simplify("0 - (x + +y)"), "-(x + y)");
+        assert_eq!(simplify("max(x * 1, 0 * x)"), "max(x, 0 * x)");
+    }
+
+    #[test]
+    fn test_tree() {
+        assert_eq!(
+            parse::<i32>("-a + f(2, 3 * b)").tree(),
+            "+
+├─ -
+│  └─ a
+└─ f()
+   ├─ 2
+   └─ *
+      ├─ 3
+      └─ b
+"
+        );
+    }
+}
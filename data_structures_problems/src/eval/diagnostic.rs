@@ -0,0 +1,172 @@
+//! Shows errors under the part of the line they are about, with a hint when
+//! there is an obvious fix.
+
+use std::fmt::Write;
+
+use colored::Colorize;
+use unicode_width::UnicodeWidthChar;
+
+use super::{ExprError, ExprErrorType};
+
+/// How many characters of a long line are shown.
+const WINDOW: usize = 60;
+/// How many of them come before the error when the start is cut off.
+const BEFORE: usize = 20;
+
+const TWO_CHAR_OPERATORS: [&str; 10] = ["**", "//", "<<", ">>", "<=", ">=", "==", "!=", "&&", "||"];
+
+/// Renders each of `errors` in `line`.
+pub fn render(errors: &[ExprError], line: &str) -> String {
+    let chars: Vec<char> = line.chars().collect();
+    let mut s = String::new();
+    for e in errors {
+        render_one(&mut s, e, &chars).unwrap();
+    }
+    s
+}
+
+fn render_one(s: &mut String, e: &ExprError, chars: &[char]) -> std::fmt::Result {
+    let pos = e.pos.min(chars.len());
+    let first = if pos < WINDOW - BEFORE {
+        0
+    } else {
+        pos - BEFORE
+    };
+    let last = chars.len().min(first + WINDOW);
+    let end = (pos + token_len(chars, pos)).min(last);
+
+    writeln!(s, "{}: {}", "error".red(), e)?;
+    let shown: String = chars[first..last]
+        .iter()
+        .map(|&c| if c == '\t' { ' ' } else { c })
+        .collect();
+    writeln!(
+        s,
+        "{}{}{}",
+        if first == 0 { "    " } else { "... " },
+        shown,
+        if last < chars.len() { " ..." } else { "" }
+    )?;
+    let column = width(&chars[first..pos]);
+    let underline = "^".repeat(width(&chars[pos..end]).max(1));
+    match hint(e, chars) {
+        Some(hint) => writeln!(s, "    {}{} {}", " ".repeat(column), underline, hint),
+        None => writeln!(s, "    {}{}", " ".repeat(column), underline),
+    }
+}
+
+/// How many columns `chars` take in a terminal.
+fn width(chars: &[char]) -> usize {
+    chars
+        .iter()
+        .map(|&c| if c == '\t' { 1 } else { c.width().unwrap_or(0) })
+        .sum()
+}
+
+/// How many characters the token at `pos` takes, to underline all of it.
+fn token_len(chars: &[char], pos: usize) -> usize {
+    let rest = &chars[pos..];
+    let is_word = |c: &char| c.is_alphanumeric() || *c == '_' || *c == '.';
+    match rest.first() {
+        None => 0,
+        Some(c) if is_word(c) => rest.iter().take_while(|c| is_word(c)).count(),
+        Some(_) => {
+            let two: String = rest.iter().take(2).collect();
+            if TWO_CHAR_OPERATORS.contains(&two.as_str()) {
+                2
+            } else {
+                1
+            }
+        }
+    }
+}
+
+fn hint(e: &ExprError, chars: &[char]) -> Option<String> {
+    let suggest = |replacement| Some(format!("did you mean `{}`?", replacement));
+    match &e.what {
+        ExprErrorType::IllegalChar => match chars.get(e.pos)? {
+            '×' | '·' | '⋅' => suggest("*"),
+            '÷' => suggest("/"),
+            '−' | '–' => suggest("-"),
+            '=' => suggest("=="),
+            '≠' => suggest("!="),
+            '≤' => suggest("<="),
+            '≥' => suggest(">="),
+            '[' | '{' => suggest("("),
+            ']' | '}' => suggest(")"),
+            _ => None,
+        },
+        // Like the `x` in `2 x`.
+        ExprErrorType::UnexpectedOperand | ExprErrorType::TooManyOperands => {
+            Some("did you mean `*` before it?".to_owned())
+        }
+        ExprErrorType::MissingColon => Some("add `: <expression>` after it".to_owned()),
+        ExprErrorType::I32Overflow | ExprErrorType::Unsupported(_) => {
+            Some("try --bigint".to_owned())
+        }
+        ExprErrorType::UnknownVariable(name) => {
+            Some(format!("define it with `let {} = <expression>`", name))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn error(what: ExprErrorType, pos: usize) -> ExprError {
+        ExprError { what, pos }
+    }
+
+    #[test]
+    fn test_render() {
+        colored::control::set_override(false);
+        assert_eq!(
+            render(&[error(ExprErrorType::IllegalChar, 4)], "1 + × 2"),
+            "error: illegal character, at 4
+    1 + × 2
+        ^ did you mean `*`?
+"
+        );
+        // Wide characters take two columns, and the operator is underlined.
+        assert_eq!(
+            render(
+                &[
+                    error(ExprErrorType::UnknownFunction("数".to_owned()), 1),
+                    error(ExprErrorType::MissingOperand, 6)
+                ],
+                "(数(1) <= )"
+            ),
+            "error: unknown function `数`, at 1
+    (数(1) <= )
+     ^^
+error: missing an operand, at 6
+    (数(1) <= )
+           ^^
+"
+        );
+        assert_eq!(
+            render(&[error(ExprErrorType::UnmatchedParenthesis, 3)], "1 +"),
+            "error: unmatched parenthesis, at 3
+    1 +
+       ^
+"
+        );
+
+        let line = format!("{}y", "1 + ".repeat(30));
+        assert_eq!(
+            render(
+                &[error(ExprErrorType::UnknownVariable("y".to_owned()), 120)],
+                &line
+            ),
+            format!(
+                "error: unknown variable `y`, at 120
+... {}y
+                        ^ define it with `let y = <expression>`
+",
+                "1 + ".repeat(5)
+            )
+        );
+    }
+}
@@ -0,0 +1,57 @@
+use std::collections::HashMap;
+
+use super::functions::Function;
+
+/// The variables and functions of a session, which outlive the line defining
+/// them.
+pub struct Environment<N> {
+    variables: HashMap<String, N>,
+    functions: HashMap<String, Function<N>>,
+    /// The result of the last expression.
+    ans: Option<N>,
+}
+
+impl<N> Environment<N> {
+    pub fn new() -> Self {
+        Environment {
+            variables: HashMap::new(),
+            functions: HashMap::new(),
+            ans: None,
+        }
+    }
+
+    /// `_` and `ans` both name the previous result.
+    pub fn get(&self, name: &str) -> Option<&N> {
+        match name {
+            "_" | "ans" => self.ans.as_ref(),
+            _ => self.variables.get(name),
+        }
+    }
+
+    pub fn set(&mut self, name: String, value: N) {
+        self.variables.insert(name, value);
+    }
+
+    pub fn set_ans(&mut self, value: N) {
+        self.ans = Some(value);
+    }
+
+    pub fn function(&self, name: &str) -> Option<&Function<N>> {
+        self.functions.get(name)
+    }
+
+    pub fn define(&mut self, name: String, function: Function<N>) {
+        self.functions.insert(name, function);
+    }
+}
+
+/// Whether `name` can only be read.
+pub fn is_read_only(name: &str) -> bool {
+    matches!(name, "_" | "ans")
+}
+
+impl<N> Default for Environment<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,104 @@
+//! The functions `eval` can call: a few built-in ones and those defined with
+//! `fn name(parameters) = expression`.
+
+use std::fmt::Display;
+
+use super::{ast::Expr, number::Number, Operator};
+
+/// How many arguments a function takes.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Arity {
+    Exactly(usize),
+    AtLeast(usize),
+}
+
+impl Arity {
+    pub fn accepts(&self, count: usize) -> bool {
+        match *self {
+            Arity::Exactly(n) => count == n,
+            Arity::AtLeast(n) => count >= n,
+        }
+    }
+}
+
+impl Display for Arity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Arity::Exactly(n) => write!(f, "{}", n),
+            Arity::AtLeast(n) => write!(f, "at least {}", n),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Builtin {
+    Abs,
+    Min,
+    Max,
+    Pow,
+    Gcd,
+    /// Only for floats.
+    Sqrt,
+}
+
+impl Builtin {
+    pub fn from_name(name: &str) -> Option<Self> {
+        let builtin = match name {
+            "abs" => Builtin::Abs,
+            "min" => Builtin::Min,
+            "max" => Builtin::Max,
+            "pow" => Builtin::Pow,
+            "gcd" => Builtin::Gcd,
+            "sqrt" => Builtin::Sqrt,
+            _ => return None,
+        };
+        Some(builtin)
+    }
+
+    pub fn arity(&self) -> Arity {
+        match self {
+            Builtin::Abs | Builtin::Sqrt => Arity::Exactly(1),
+            Builtin::Pow | Builtin::Gcd => Arity::Exactly(2),
+            Builtin::Min | Builtin::Max => Arity::AtLeast(1),
+        }
+    }
+
+    /// `args` should be accepted by `arity`. Returns `None` if the result is
+    /// undefined or out of range.
+    pub fn call<N: Number>(&self, args: &[N]) -> Option<N> {
+        let pick = |better: fn(&N, &N) -> bool| {
+            args.iter()
+                .fold(&args[0], |a, b| if better(b, a) { b } else { a })
+                .clone()
+        };
+        match self {
+            Builtin::Abs => args[0].abs(),
+            Builtin::Min => Some(pick(|a, b| a < b)),
+            Builtin::Max => Some(pick(|a, b| a > b)),
+            Builtin::Pow => N::apply(&Operator::Power, &args[0], &args[1]),
+            Builtin::Gcd => args[0].gcd(&args[1]),
+            Builtin::Sqrt => args[0].sqrt(),
+        }
+    }
+}
+
+impl Display for Builtin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Builtin::Abs => "abs",
+            Builtin::Min => "min",
+            Builtin::Max => "max",
+            Builtin::Pow => "pow",
+            Builtin::Gcd => "gcd",
+            Builtin::Sqrt => "sqrt",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A function defined in the REPL. Its body may use its parameters and the
+/// variables of the session.
+pub struct Function<N> {
+    pub params: Vec<String>,
+    pub body: Expr<N>,
+}
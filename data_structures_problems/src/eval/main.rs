@@ -0,0 +1,1921 @@
+use std::{
+    env,
+    fmt::Display,
+    io::{self, BufRead},
+    process::exit,
+    thread,
+};
+
+use colored::Colorize;
+use data_structures::{
+    seq_list::SeqList,
+    stack::{SeqStack, Stack},
+};
+use num_rational::BigRational;
+
+use ast::Expr;
+use environment::Environment;
+use functions::{Arity, Builtin, Function};
+use number::Number;
+
+mod ast;
+mod diagnostic;
+mod environment;
+mod functions;
+mod number;
+
+fn main() {
+    let args = match get_args() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("Invalid args: {}", e);
+            usage();
+            exit(1)
+        }
+    };
+    let run = move || match args.mode {
+        Mode::Integer => run::<i32>(&args),
+        Mode::Float => run::<f64>(&args),
+        Mode::BigInt => run::<BigRational>(&args),
+    };
+    let evaluator = thread::Builder::new().stack_size(STACK_SIZE).spawn(run);
+    if evaluator.unwrap().join().is_err() {
+        exit(101)
+    }
+}
+
+/// Plenty for `MAX_NESTING` levels of `eval_expr`, even in a debug build. Only
+/// the part in use is backed by memory.
+const STACK_SIZE: usize = 256 << 20;
+
+fn usage() {
+    println!(
+        "Usage: {} [--float | --bigint] [--output-radix <2|8|10|16>] [--show-postfix] [--show-ast] | -h/--help
+Besides the binary, unary and `?:` operators of C, where `^` is the bitwise xor,
+`**` raises to a power and `//` divides rounding down.",
+        env::args().next().unwrap()
+    )
+}
+
+enum Mode {
+    Integer,
+    Float,
+    BigInt,
+}
+
+struct Args {
+    mode: Mode,
+    output_radix: u32,
+    /// Print the postfix form of each expression.
+    show_postfix: bool,
+    /// Print the expression tree of each expression and what it simplifies
+    /// to.
+    show_ast: bool,
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Args {
+            mode: Mode::Integer,
+            output_radix: 10,
+            show_postfix: false,
+            show_ast: false,
+        }
+    }
+}
+
+fn get_args() -> Result<Args, &'static str> {
+    let mut args = Args::default();
+    let mut options = env::args().skip(1);
+    while let Some(option) = options.next() {
+        match option.as_ref() {
+            "--float" => args.mode = Mode::Float,
+            "--bigint" => args.mode = Mode::BigInt,
+            "--output-radix" => {
+                let radix = options.next().ok_or("Missing a value.")?;
+                args.output_radix = match radix.as_ref() {
+                    "2" | "8" | "10" | "16" => radix.parse().unwrap(),
+                    _ => return Err("The output radix should be 2, 8, 10 or 16."),
+                };
+            }
+            "--show-postfix" => args.show_postfix = true,
+            "--show-ast" => args.show_ast = true,
+            "-h" | "--help" => {
+                usage();
+                exit(0)
+            }
+            _ => return Err("Unknown option."),
+        }
+    }
+    Ok(args)
+}
+
+fn run<N: Number>(args: &Args) {
+    let stdin = io::stdin();
+    let handle = stdin.lock();
+    let mut lines = handle.lines();
+    let mut env = Environment::<N>::new();
+    while let Some(Ok(expression)) = lines.next() {
+        if !expression.is_empty() {
+            match eval_and_print(&expression, &mut env, args) {
+                Some(Value::Number(result)) => {
+                    match number::to_string_radix(&result, args.output_radix) {
+                        Some(s) => println!("{}", s),
+                        None => println!(
+                            "{}: {} is not an integer, so it cannot be printed in base {}",
+                            "error".red(),
+                            result,
+                            args.output_radix
+                        ),
+                    }
+                }
+                Some(Value::Boolean(b)) => println!("{}", b),
+                None => (),
+            }
+        }
+    }
+}
+
+/// The result of a line. Comparisons and logical operators give booleans,
+/// which are kept as 1 and 0.
+#[derive(Debug, PartialEq)]
+enum Value<N> {
+    Number(N),
+    Boolean(bool),
+}
+
+/// Evaluates a line, which either defines a variable or a function, or
+/// yields a result to print.
+fn eval_and_print<N: Number>(
+    statement: &str,
+    env: &mut Environment<N>,
+    args: &Args,
+) -> Option<Value<N>> {
+    let (target, start) = get_assignment_target(statement)
+        .map_err(|e| print_errors(&[e], statement))
+        .ok()?;
+    let postfix = infix_to_postfix_from(statement, start)
+        .map_err(|errors| print_errors(&errors, statement))
+        .ok()?;
+    show(&postfix, args);
+    let expr = Expr::from_postfix(&postfix)
+        .map_err(|e| print_errors(&[e.into_expr_error()], statement))
+        .ok()?;
+    if let Some(Target::Function(name, params)) = target {
+        check_variables(&postfix, &params, env)
+            .map_err(|errors| print_errors(&errors, statement))
+            .ok()?;
+        env.define(name, Function { params, body: expr });
+        return None;
+    }
+    let value = eval_expr(&expr, env, &Frame::top_level())
+        .map_err(|e| print_errors(&[e.into_expr_error()], statement))
+        .ok()?;
+    match target {
+        Some(Target::Variable(name)) => {
+            env.set(name, value);
+            None
+        }
+        _ => {
+            env.set_ans(value.clone());
+            if expr.is_boolean() {
+                Some(Value::Boolean(value.is_true()))
+            } else {
+                Some(Value::Number(value))
+            }
+        }
+    }
+}
+
+fn show<N: Number>(postfix: &[Token<N>], args: &Args) {
+    if args.show_postfix {
+        let tokens: Vec<String> = postfix.iter().map(|token| token.to_string()).collect();
+        println!("{} {}", "postfix:".blue(), tokens.join(" "));
+    }
+    if args.show_ast {
+        // A malformed expression gets its error from the evaluation.
+        if let Ok(expr) = Expr::from_postfix(postfix) {
+            print!("{}\n{}", "ast:".blue(), expr.tree());
+            println!("{} {}", "infix:".blue(), expr);
+            println!("{} {}", "simplified:".blue(), expr.fold().simplify());
+        }
+    }
+}
+
+/// Checks that a function body only uses its parameters and the variables
+/// that exist, so that no error points into a line that is gone.
+fn check_variables<N>(
+    body: &[Token<N>],
+    params: &[String],
+    env: &Environment<N>,
+) -> Result<(), Vec<ExprError>> {
+    let errors: Vec<ExprError> = body
+        .iter()
+        .filter_map(|token| match token {
+            Token::Variable(name, pos) if !params.contains(name) && env.get(name).is_none() => {
+                Some(ExprError {
+                    what: ExprErrorType::UnknownVariable(name.clone()),
+                    pos: *pos,
+                })
+            }
+            _ => None,
+        })
+        .collect();
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+fn print_errors(errors: &[ExprError], statement: &str) {
+    print!("{}", diagnostic::render(errors, statement));
+}
+
+#[derive(PartialEq, Clone, Debug)]
+enum Operator {
+    Add,
+    Subtract,
+    Multiply,
+    /// Truncating division, like `/` in C.
+    Divide,
+    /// `//`, which rounds towards negative infinity.
+    FloorDivide,
+    /// The remainder of `Divide`.
+    Remainder,
+    /// `**`.
+    Power,
+    BitAnd,
+    BitOr,
+    /// `^`, like in C.
+    BitXor,
+    ShiftLeft,
+    ShiftRight,
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    /// `&&`, which only evaluates its right operand if the left one is true.
+    And,
+    /// `||`, which only evaluates its right operand if the left one is false.
+    Or,
+    /// Unary minus.
+    Negate,
+    /// Unary plus.
+    Plus,
+    /// `!`.
+    Not,
+    /// `a ? b : c`, which only evaluates one of `b` and `c`.
+    Conditional,
+}
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+enum Associativity {
+    Left,
+    Right,
+}
+
+impl Operator {
+    /// The binary operators bind like in C, except that `Power` binds
+    /// tighter than the unary ones, so `-2**2` is `-4`.
+    fn precedence(&self) -> u32 {
+        match self {
+            Operator::Conditional => 1,
+            Operator::Or => 2,
+            Operator::And => 3,
+            Operator::BitOr => 4,
+            Operator::BitXor => 5,
+            Operator::BitAnd => 6,
+            Operator::Equal | Operator::NotEqual => 7,
+            Operator::Less | Operator::LessEqual | Operator::Greater | Operator::GreaterEqual => 8,
+            Operator::ShiftLeft | Operator::ShiftRight => 9,
+            Operator::Add | Operator::Subtract => 10,
+            Operator::Multiply | Operator::Divide | Operator::FloorDivide | Operator::Remainder => {
+                11
+            }
+            Operator::Negate | Operator::Plus | Operator::Not => 12,
+            Operator::Power => 13,
+        }
+    }
+
+    fn associativity(&self) -> Associativity {
+        match self {
+            Operator::Power
+            | Operator::Negate
+            | Operator::Plus
+            | Operator::Not
+            | Operator::Conditional => Associativity::Right,
+            _ => Associativity::Left,
+        }
+    }
+
+    fn is_unary(&self) -> bool {
+        matches!(self, Operator::Negate | Operator::Plus | Operator::Not)
+    }
+
+    /// Whether the result is a truth value.
+    fn is_logical(&self) -> bool {
+        matches!(
+            self,
+            Operator::Equal
+                | Operator::NotEqual
+                | Operator::Less
+                | Operator::LessEqual
+                | Operator::Greater
+                | Operator::GreaterEqual
+                | Operator::And
+                | Operator::Or
+                | Operator::Not
+        )
+    }
+}
+
+impl PartialOrd for Operator {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        u32::partial_cmp(&self.precedence(), &other.precedence())
+    }
+}
+
+impl Display for Operator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let symbol = match self {
+            Operator::Add | Operator::Plus => "+",
+            Operator::Subtract | Operator::Negate => "-",
+            Operator::Multiply => "*",
+            Operator::Divide => "/",
+            Operator::FloorDivide => "//",
+            Operator::Remainder => "%",
+            Operator::Power => "**",
+            Operator::BitAnd => "&",
+            Operator::BitOr => "|",
+            Operator::BitXor => "^",
+            Operator::ShiftLeft => "<<",
+            Operator::ShiftRight => ">>",
+            Operator::Equal => "==",
+            Operator::NotEqual => "!=",
+            Operator::Less => "<",
+            Operator::LessEqual => "<=",
+            Operator::Greater => ">",
+            Operator::GreaterEqual => ">=",
+            Operator::And => "&&",
+            Operator::Or => "||",
+            Operator::Not => "!",
+            Operator::Conditional => "?:",
+        };
+        write!(f, "{}", symbol)
+    }
+}
+
+#[derive(PartialEq, Clone, Debug)]
+enum Token<N> {
+    /// An operator and where it is.
+    Operator(Operator, usize),
+    /// A number and where it is.
+    Num(N, usize),
+    /// A name and where it is.
+    Variable(String, usize),
+    /// The name of a function, the number of arguments and where it is.
+    Call(String, usize, usize),
+}
+
+/// Unary operators are written `u-` and `u+`, and calls like `max/3`.
+impl<N: Display> Display for Token<N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Operator(op, _) if op.is_unary() => write!(f, "u{}", op),
+            Token::Operator(op, _) => write!(f, "{}", op),
+            Token::Num(n, _) => write!(f, "{}", n),
+            Token::Variable(name, _) => write!(f, "{}", name),
+            Token::Call(name, count, _) => write!(f, "{}/{}", name, count),
+        }
+    }
+}
+
+const RADIX: u32 = 10;
+
+enum NonNumber {
+    /// An operator and where it is.
+    Operator(Operator, usize),
+    LeftParenthesis(usize),
+    /// The `?` of a conditional whose `:` is yet to come.
+    Question(usize),
+    /// The `(` of a call, which also counts the `,`s.
+    Call {
+        name: String,
+        pos: usize,
+        paren: usize,
+        commas: usize,
+    },
+}
+
+#[derive(Debug, PartialEq)]
+struct ExprError {
+    what: ExprErrorType,
+    pos: usize,
+}
+
+impl Display for ExprError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}, at {}", self.what, self.pos.to_string().blue())
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum ExprErrorType {
+    IllegalChar,
+    UnmatchedParenthesis,
+    I32Overflow,
+    /// A fraction or an exponent in integer mode.
+    NotAnInteger,
+    NumberTooLarge,
+    /// Like `0x` without digits or `0b12`.
+    MalformedNumber,
+    UnknownVariable(String),
+    /// A `let` without a name or an `=`.
+    InvalidAssignment,
+    /// An assignment to `_` or `ans`.
+    ReadOnlyVariable,
+    UnknownFunction(String),
+    WrongArity {
+        name: String,
+        expected: Arity,
+        found: usize,
+    },
+    /// A `fn` without a name, parameters or an `=`.
+    InvalidDefinition,
+    BuiltinRedefinition,
+    /// A `,` outside of the parentheses of a call.
+    UnexpectedComma,
+    RecursionLimit,
+    /// An expression nested too deeply to evaluate.
+    NestingLimit,
+    /// A `?` without a `:`.
+    MissingColon,
+    /// A `:` without a `?`.
+    UnexpectedColon,
+    /// An operator or a call without enough operands.
+    MissingOperand,
+    /// An operand that no operator takes.
+    TooManyOperands,
+    /// An operand right after another one, like the `x` in `2 x`.
+    UnexpectedOperand,
+    /// A binary operator without a left operand, like the `*` in `1 + * 2`.
+    UnexpectedOperator,
+    EmptyParentheses,
+    /// An operation that is undefined or out of range, as written.
+    Undefined(String),
+    /// An operator not defined for this kind of number.
+    Unsupported(Operator),
+}
+
+impl Display for ExprErrorType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let description = match self {
+            ExprErrorType::UnknownVariable(name) => {
+                return write!(f, "unknown variable `{}`", name);
+            }
+            ExprErrorType::UnknownFunction(name) => {
+                return write!(f, "unknown function `{}`", name);
+            }
+            ExprErrorType::Undefined(operation) => {
+                return write!(f, "`{}` is undefined or out of range", operation);
+            }
+            ExprErrorType::Unsupported(op) => {
+                return write!(f, "`{}` is not supported for this kind of number", op);
+            }
+            ExprErrorType::WrongArity {
+                name,
+                expected,
+                found,
+            } => {
+                return write!(
+                    f,
+                    "`{}` takes {} argument(s) but got {}",
+                    name, expected, found
+                );
+            }
+            ExprErrorType::InvalidDefinition => "expected `fn <name>(<parameters>) = <expression>`",
+            ExprErrorType::BuiltinRedefinition => "cannot redefine a built-in function",
+            ExprErrorType::UnexpectedComma => "comma outside of a function call",
+            ExprErrorType::RecursionLimit => "too many nested calls",
+            ExprErrorType::NestingLimit => "expression nested too deeply",
+            ExprErrorType::MissingColon => "`?` without a matching `:`",
+            ExprErrorType::UnexpectedColon => "`:` without a matching `?`",
+            ExprErrorType::MissingOperand => "missing an operand",
+            ExprErrorType::TooManyOperands => "operand without an operator",
+            ExprErrorType::UnexpectedOperand => "expected an operator before this operand",
+            ExprErrorType::UnexpectedOperator => "expected an operand before this operator",
+            ExprErrorType::EmptyParentheses => "empty parentheses",
+            ExprErrorType::InvalidAssignment => "expected `let <name> = <expression>`",
+            ExprErrorType::ReadOnlyVariable => "cannot assign to the previous result",
+            ExprErrorType::IllegalChar => "illegal character",
+            ExprErrorType::UnmatchedParenthesis => "unmatched parenthesis",
+            ExprErrorType::I32Overflow => "32-bit signed integer overflow",
+            ExprErrorType::NotAnInteger => "not an integer, try --float or --bigint",
+            ExprErrorType::NumberTooLarge => "number too large",
+            ExprErrorType::MalformedNumber => "malformed number",
+        };
+        write!(f, "{}", description)
+    }
+}
+
+fn is_identifier_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_'
+}
+
+/// Reads an identifier starting with `c`.
+fn get_identifier<T: Iterator<Item = (usize, char)>>(
+    c: char,
+    i: &mut std::iter::Peekable<T>,
+) -> String {
+    let mut name = String::from(c);
+    while let Some((_, c)) = i.next_if(|(_, c)| c.is_alphanumeric() || *c == '_') {
+        name.push(c);
+    }
+    name
+}
+
+fn skip_whitespace<T: Iterator<Item = (usize, char)>>(i: &mut std::iter::Peekable<T>) {
+    while i.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+}
+
+/// What a statement defines.
+#[derive(Debug, PartialEq)]
+enum Target {
+    Variable(String),
+    /// The name and the parameters.
+    Function(String, Vec<String>),
+}
+
+fn get_identifier_at<T: Iterator<Item = (usize, char)>>(
+    i: &mut std::iter::Peekable<T>,
+) -> Option<(usize, String)> {
+    let (pos, c) = i.next_if(|&(_, c)| is_identifier_start(c))?;
+    Some((pos, get_identifier(c, i)))
+}
+
+/// Finds what a statement like `let x = 1`, `x = 1` or `fn f(x) = x` defines,
+/// and the position its expression starts at.
+fn get_assignment_target(s: &str) -> Result<(Option<Target>, usize), ExprError> {
+    let end = s.chars().count();
+    let mut i = s.chars().enumerate().peekable();
+    let error_at = |what, next: Option<(usize, char)>| ExprError {
+        what,
+        pos: next.map_or(end, |(pos, _)| pos),
+    };
+    skip_whitespace(&mut i);
+    let (pos, name) = match get_identifier_at(&mut i) {
+        Some(identifier) => identifier,
+        None => return Ok((None, 0)),
+    };
+    let is_let = name == "let";
+    let (pos, target) = match name.as_ref() {
+        "let" => {
+            skip_whitespace(&mut i);
+            let (pos, name) = get_identifier_at(&mut i)
+                .ok_or_else(|| error_at(ExprErrorType::InvalidAssignment, i.peek().copied()))?;
+            (pos, Target::Variable(name))
+        }
+        "fn" => {
+            let invalid = |i: &mut std::iter::Peekable<_>| {
+                error_at(ExprErrorType::InvalidDefinition, i.peek().copied())
+            };
+            skip_whitespace(&mut i);
+            let (pos, name) = get_identifier_at(&mut i).ok_or_else(|| invalid(&mut i))?;
+            if Builtin::from_name(&name).is_some() {
+                return Err(ExprError {
+                    what: ExprErrorType::BuiltinRedefinition,
+                    pos,
+                });
+            }
+            skip_whitespace(&mut i);
+            i.next_if(|&(_, c)| c == '(')
+                .ok_or_else(|| invalid(&mut i))?;
+            let mut params = Vec::new();
+            loop {
+                skip_whitespace(&mut i);
+                if params.is_empty() && i.next_if(|&(_, c)| c == ')').is_some() {
+                    break;
+                }
+                match get_identifier_at(&mut i) {
+                    Some((pos, param)) if params.contains(&param) => {
+                        return Err(ExprError {
+                            what: ExprErrorType::InvalidDefinition,
+                            pos,
+                        })
+                    }
+                    Some((_, param)) => params.push(param),
+                    None => return Err(invalid(&mut i)),
+                }
+                skip_whitespace(&mut i);
+                match i.next() {
+                    Some((_, ',')) => (),
+                    Some((_, ')')) => break,
+                    next => return Err(error_at(ExprErrorType::InvalidDefinition, next)),
+                }
+            }
+            skip_whitespace(&mut i);
+            i.next_if(|&(_, c)| c == '=')
+                .ok_or_else(|| invalid(&mut i))?;
+            let start = i.peek().map_or(end, |&(pos, _)| pos);
+            return Ok((Some(Target::Function(name, params)), start));
+        }
+        _ => (pos, Target::Variable(name)),
+    };
+    skip_whitespace(&mut i);
+    match i.next() {
+        // Not `==`.
+        Some((_, '=')) if i.peek().map_or(true, |&(_, c)| c != '=') => (),
+        next if is_let => return Err(error_at(ExprErrorType::InvalidAssignment, next)),
+        _ => return Ok((None, 0)),
+    }
+    if let Target::Variable(name) = &target {
+        if environment::is_read_only(name) {
+            return Err(ExprError {
+                what: ExprErrorType::ReadOnlyVariable,
+                pos,
+            });
+        }
+    }
+    Ok((Some(target), i.peek().map_or(end, |&(pos, _)| pos)))
+}
+
+#[cfg(test)]
+fn infix_to_postfix<N: Number>(s: &str) -> Result<SeqList<Token<N>>, Vec<ExprError>> {
+    infix_to_postfix_from(s, 0)
+}
+
+/// Converts the expression starting at the `start`th character of `s`. After
+/// an error, it goes on with the rest of the expression, so that all the
+/// errors are returned at once.
+fn infix_to_postfix_from<N: Number>(
+    s: &str,
+    start: usize,
+) -> Result<SeqList<Token<N>>, Vec<ExprError>> {
+    let mut i = s.chars().enumerate().skip(start).peekable();
+    let mut ret = SeqList::new();
+    let mut stack: SeqStack<NonNumber> = SeqStack::new();
+    let mut errors = Vec::new();
+    // An operator where an operand is expected is a prefix one.
+    let mut expect_operand = true;
+    // Whether the last character was skipped as illegal, which already
+    // accounts for a missing operand.
+    let mut after_illegal = false;
+
+    while let Some((pos, c)) = i.next() {
+        if c.is_whitespace() {
+            continue;
+        }
+        let missing_operand = expect_operand && !std::mem::take(&mut after_illegal);
+        let starts_operand = c.is_digit(RADIX) || is_identifier_start(c) || c == '(';
+        if starts_operand && !expect_operand {
+            errors.push(ExprError {
+                what: ExprErrorType::UnexpectedOperand,
+                pos,
+            });
+        } else if matches!(c, '?' | ':') && expect_operand {
+            errors.push(ExprError {
+                what: ExprErrorType::UnexpectedOperator,
+                pos,
+            });
+        }
+        match c {
+            _ if c.is_digit(RADIX) => {
+                // A malformed number still counts as an operand.
+                let number = get_number(c, pos, &mut i).unwrap_or_else(|e| {
+                    errors.push(e);
+                    N::from_i32(0)
+                });
+                ret.push(Token::Num(number, pos));
+                expect_operand = false;
+            }
+            _ if is_identifier_start(c) => {
+                let name = get_identifier(c, &mut i);
+                // A name followed by `(` is called.
+                let mut ahead = i.clone();
+                skip_whitespace(&mut ahead);
+                if let Some((paren, _)) = ahead.next_if(|&(_, c)| c == '(') {
+                    i = ahead;
+                    stack.push(NonNumber::Call {
+                        name,
+                        pos,
+                        paren,
+                        commas: 0,
+                    });
+                    expect_operand = true;
+                } else {
+                    ret.push(Token::Variable(name, pos));
+                    expect_operand = false;
+                }
+            }
+            '(' => {
+                stack.push(NonNumber::LeftParenthesis(pos));
+                expect_operand = true;
+            }
+            ',' => {
+                if missing_operand {
+                    errors.push(ExprError {
+                        what: ExprErrorType::MissingOperand,
+                        pos,
+                    });
+                }
+                pop_operators(&mut stack, &mut ret);
+                match stack.peek_mut() {
+                    Some(NonNumber::Call { commas, .. }) => *commas += 1,
+                    _ => errors.push(ExprError {
+                        what: ExprErrorType::UnexpectedComma,
+                        pos,
+                    }),
+                }
+                expect_operand = true;
+            }
+            ')' => {
+                if missing_operand {
+                    match stack.peek() {
+                        Some(&NonNumber::LeftParenthesis(open)) => errors.push(ExprError {
+                            what: ExprErrorType::EmptyParentheses,
+                            pos: open,
+                        }),
+                        // A call without arguments.
+                        Some(NonNumber::Call { commas: 0, .. }) => (),
+                        _ => errors.push(ExprError {
+                            what: ExprErrorType::MissingOperand,
+                            pos,
+                        }),
+                    }
+                }
+                close_parenthesis(&mut stack, &mut ret, expect_operand, pos, &mut errors);
+                expect_operand = false;
+            }
+            '?' => {
+                // Conditionals nest to the right, so only the operators that
+                // bind tighter end here.
+                while let Some(NonNumber::Operator(top, top_pos)) = stack.peek() {
+                    if top <= &Operator::Conditional {
+                        break;
+                    }
+                    ret.push(Token::Operator(top.clone(), *top_pos));
+                    stack.pop();
+                }
+                stack.push(NonNumber::Question(pos));
+                expect_operand = true;
+            }
+            ':' => {
+                pop_operators(&mut stack, &mut ret);
+                if let Some(&NonNumber::Question(question)) = stack.peek() {
+                    stack.pop();
+                    stack.push(NonNumber::Operator(Operator::Conditional, question));
+                } else {
+                    errors.push(ExprError {
+                        what: ExprErrorType::UnexpectedColon,
+                        pos,
+                    });
+                }
+                expect_operand = true;
+            }
+            _ => match get_operator(c, &mut i, expect_operand) {
+                Ok(op) => {
+                    if expect_operand && !op.is_unary() {
+                        errors.push(ExprError {
+                            what: ExprErrorType::UnexpectedOperator,
+                            pos,
+                        });
+                    }
+                    handle_an_operator(&mut stack, op, pos, &mut ret);
+                    expect_operand = true;
+                }
+                Err(what) => {
+                    errors.push(ExprError { what, pos });
+                    // Most likely a mistyped operator.
+                    expect_operand = true;
+                    after_illegal = true;
+                }
+            },
+        }
+    }
+    if expect_operand && !after_illegal {
+        errors.push(ExprError {
+            what: ExprErrorType::MissingOperand,
+            pos: s.chars().count(),
+        });
+    }
+    while let Some(non_number) = stack.pop() {
+        match non_number {
+            NonNumber::Operator(op, pos) => ret.push(Token::Operator(op, pos)),
+            NonNumber::LeftParenthesis(pos) | NonNumber::Call { paren: pos, .. } => {
+                errors.push(ExprError {
+                    what: ExprErrorType::UnmatchedParenthesis,
+                    pos,
+                })
+            }
+            NonNumber::Question(pos) => errors.push(ExprError {
+                what: ExprErrorType::MissingColon,
+                pos,
+            }),
+        }
+    }
+    if errors.is_empty() {
+        Ok(ret)
+    } else {
+        errors.sort_by_key(|e| e.pos);
+        Err(errors)
+    }
+}
+
+/// Pops the operators down to the first `(`, `?` or the bottom.
+fn pop_operators<N>(stack: &mut SeqStack<NonNumber>, ret: &mut SeqList<Token<N>>) {
+    while let Some(NonNumber::Operator(op, pos)) = stack.peek() {
+        ret.push(Token::Operator(op.clone(), *pos));
+        stack.pop();
+    }
+}
+
+/// Pops the operators down to the matching `(` of the `)` at `pos`, and ends
+/// the call it opens if any. A call is `empty` if no operand follows its `(`.
+fn close_parenthesis<N>(
+    stack: &mut SeqStack<NonNumber>,
+    ret: &mut SeqList<Token<N>>,
+    empty: bool,
+    pos: usize,
+    errors: &mut Vec<ExprError>,
+) {
+    while let Some(n) = stack.pop() {
+        match n {
+            NonNumber::LeftParenthesis(_) => return,
+            NonNumber::Call {
+                name, pos, commas, ..
+            } => {
+                let args = if empty && commas == 0 { 0 } else { commas + 1 };
+                ret.push(Token::Call(name, args, pos));
+                return;
+            }
+            NonNumber::Operator(op, pos) => {
+                ret.push(Token::Operator(op, pos));
+            }
+            NonNumber::Question(pos) => errors.push(ExprError {
+                what: ExprErrorType::MissingColon,
+                pos,
+            }),
+        }
+    }
+    errors.push(ExprError {
+        what: ExprErrorType::UnmatchedParenthesis,
+        pos,
+    })
+}
+
+/// Reads the operator starting with `c`, which is a prefix one if `prefix`.
+fn get_operator<T: Iterator<Item = (usize, char)>>(
+    c: char,
+    i: &mut std::iter::Peekable<T>,
+    prefix: bool,
+) -> Result<Operator, ExprErrorType> {
+    let mut followed_by = |next: char| i.next_if(|&(_, c)| c == next).is_some();
+    let op = match (c, prefix) {
+        ('+', true) => Operator::Plus,
+        ('-', true) => Operator::Negate,
+        ('+', false) => Operator::Add,
+        ('-', false) => Operator::Subtract,
+        ('*', _) if followed_by('*') => Operator::Power,
+        ('*', _) => Operator::Multiply,
+        ('/', _) if followed_by('/') => Operator::FloorDivide,
+        ('/', _) => Operator::Divide,
+        ('%', _) => Operator::Remainder,
+        ('^', _) => Operator::BitXor,
+        ('!', true) => Operator::Not,
+        ('!', false) if followed_by('=') => Operator::NotEqual,
+        ('=', false) if followed_by('=') => Operator::Equal,
+        ('&', _) if followed_by('&') => Operator::And,
+        ('&', _) => Operator::BitAnd,
+        ('|', _) if followed_by('|') => Operator::Or,
+        ('|', _) => Operator::BitOr,
+        ('<', _) if followed_by('<') => Operator::ShiftLeft,
+        ('<', _) if followed_by('=') => Operator::LessEqual,
+        ('<', _) => Operator::Less,
+        ('>', _) if followed_by('>') => Operator::ShiftRight,
+        ('>', _) if followed_by('=') => Operator::GreaterEqual,
+        ('>', _) => Operator::Greater,
+        _ => return Err(ExprErrorType::IllegalChar),
+    };
+    Ok(op)
+}
+
+fn handle_an_operator<N>(
+    stack: &mut SeqStack<NonNumber>,
+    current_op: Operator,
+    pos: usize,
+    postfix: &mut SeqList<Token<N>>,
+) {
+    // A prefix operator has no left operand to take from the operators on
+    // the stack.
+    while let Some(NonNumber::Operator(top, top_pos)) =
+        stack.peek().filter(|_| !current_op.is_unary())
+    {
+        let pops = match current_op.associativity() {
+            Associativity::Left => top >= &current_op,
+            Associativity::Right => top > &current_op,
+        };
+        if !pops {
+            break;
+        }
+        postfix.push(Token::Operator(top.clone(), *top_pos));
+        stack.pop();
+    }
+    stack.push(NonNumber::Operator(current_op, pos));
+}
+
+/// Reads a literal starting with the digit `c` at `pos`, like `1_000`, `1.5`,
+/// `2e-3` or `0xff`.
+fn get_number<N: Number, T: Iterator<Item = (usize, char)> + Clone>(
+    c: char,
+    pos: usize,
+    i: &mut std::iter::Peekable<T>,
+) -> Result<N, ExprError> {
+    if c == '0' {
+        if let Some((_, prefix)) =
+            i.next_if(|(_, c)| matches!(c, 'x' | 'X' | 'o' | 'O' | 'b' | 'B'))
+        {
+            let radix = match prefix.to_ascii_lowercase() {
+                'x' => 16,
+                'o' => 8,
+                _ => 2,
+            };
+            return get_prefixed_number(radix, pos, i);
+        }
+    }
+
+    let mut literal = String::from(c);
+    // `_`s may separate the digits.
+    let take_digits = |i: &mut std::iter::Peekable<T>, literal: &mut String| {
+        while let Some((_, c)) = i.next_if(|(_, c)| c.is_digit(RADIX) || *c == '_') {
+            if c != '_' {
+                literal.push(c);
+            }
+        }
+    };
+    take_digits(i, &mut literal);
+    if let Some((_, c)) = i.next_if(|&(_, c)| c == '.') {
+        literal.push(c);
+        take_digits(i, &mut literal);
+    }
+    // Only take the `e` if an exponent follows.
+    let mut exponent = i.clone();
+    if let Some((_, e)) = exponent.next_if(|(_, c)| matches!(c, 'e' | 'E')) {
+        let sign = exponent.next_if(|(_, c)| matches!(c, '+' | '-'));
+        if exponent.peek().is_some_and(|(_, c)| c.is_digit(RADIX)) {
+            literal.push(e);
+            literal.extend(sign.map(|(_, c)| c));
+            take_digits(&mut exponent, &mut literal);
+            *i = exponent;
+        }
+    }
+    N::parse(&literal).map_err(|what| ExprError { what, pos })
+}
+
+/// Reads the digits after a `0x`, `0o` or `0b` prefix. Letters are taken
+/// too, so that `0xZZ` is reported as one malformed literal, at its first
+/// bad digit.
+fn get_prefixed_number<N: Number, T: Iterator<Item = (usize, char)>>(
+    radix: u32,
+    pos: usize,
+    i: &mut std::iter::Peekable<T>,
+) -> Result<N, ExprError> {
+    let mut digits = String::new();
+    let mut bad_digit = None;
+    while let Some((digit_pos, c)) = i.next_if(|(_, c)| c.is_alphanumeric() || *c == '_') {
+        if c == '_' {
+            continue;
+        }
+        if !c.is_digit(radix) {
+            bad_digit = bad_digit.or(Some(digit_pos));
+        }
+        digits.push(c);
+    }
+    if let Some(pos) = bad_digit {
+        return Err(ExprError {
+            what: ExprErrorType::MalformedNumber,
+            pos,
+        });
+    }
+    if digits.is_empty() {
+        return Err(ExprError {
+            what: ExprErrorType::MalformedNumber,
+            pos,
+        });
+    }
+    N::from_digits(&digits, radix).map_err(|what| ExprError { what, pos })
+}
+
+/// An error in evaluating an expression, and where in it.
+#[derive(Debug)]
+enum EvalError<N> {
+    /// An operator or a call without enough operands.
+    MissingOperand(usize),
+    Overflow(Operator, N, N, usize),
+    UnaryOverflow(Operator, N, usize),
+    /// An operand that no operator takes, and where it starts.
+    TooManyOperands(usize),
+    /// The operator is not defined for this kind of number.
+    Unsupported(Operator, usize),
+    /// The name of the variable and where it is.
+    UnknownVariable(String, usize),
+    /// The name of the function and where it is called.
+    UnknownFunction(String, usize),
+    WrongArity {
+        name: String,
+        expected: Arity,
+        found: usize,
+        pos: usize,
+    },
+    /// A built-in function that is undefined for these arguments.
+    InvalidArguments(Builtin, Vec<N>, usize),
+    RecursionLimit(usize),
+    /// Where a tree gets too tall.
+    NestingLimit(usize),
+}
+
+impl<N: Display> EvalError<N> {
+    fn into_expr_error(mut self) -> ExprError {
+        let pos = *self.pos();
+        let what = match self {
+            EvalError::MissingOperand(_) => ExprErrorType::MissingOperand,
+            EvalError::Overflow(op, a, b, _) => {
+                ExprErrorType::Undefined(format!("{} {} {}", abbreviate(a), op, abbreviate(b)))
+            }
+            EvalError::UnaryOverflow(op, a, _) => {
+                ExprErrorType::Undefined(format!("{}({})", op, abbreviate(a)))
+            }
+            EvalError::TooManyOperands(_) => ExprErrorType::TooManyOperands,
+            EvalError::Unsupported(op, _) => ExprErrorType::Unsupported(op),
+            EvalError::UnknownVariable(name, _) => ExprErrorType::UnknownVariable(name),
+            EvalError::UnknownFunction(name, _) => ExprErrorType::UnknownFunction(name),
+            EvalError::WrongArity {
+                name,
+                expected,
+                found,
+                ..
+            } => ExprErrorType::WrongArity {
+                name,
+                expected,
+                found,
+            },
+            EvalError::InvalidArguments(builtin, args, _) => {
+                let args: Vec<String> = args.iter().map(abbreviate).collect();
+                ExprErrorType::Undefined(format!("{}({})", builtin, args.join(", ")))
+            }
+            EvalError::RecursionLimit(_) => ExprErrorType::RecursionLimit,
+            EvalError::NestingLimit(_) => ExprErrorType::NestingLimit,
+        };
+        ExprError { what, pos }
+    }
+}
+
+/// Shortens a huge operand in a message to its first and last digits.
+fn abbreviate<N: Display>(n: N) -> String {
+    let s = n.to_string();
+    let chars: Vec<char> = s.chars().collect();
+    if chars.len() <= 40 {
+        return s;
+    }
+    let first: String = chars[..20].iter().collect();
+    let last: String = chars[chars.len() - 10..].iter().collect();
+    format!("{}...{}", first, last)
+}
+
+impl<N> EvalError<N> {
+    fn pos(&mut self) -> &mut usize {
+        match self {
+            EvalError::MissingOperand(pos)
+            | EvalError::Overflow(_, _, _, pos)
+            | EvalError::UnaryOverflow(_, _, pos)
+            | EvalError::TooManyOperands(pos)
+            | EvalError::Unsupported(_, pos)
+            | EvalError::UnknownVariable(_, pos)
+            | EvalError::UnknownFunction(_, pos)
+            | EvalError::WrongArity { pos, .. }
+            | EvalError::InvalidArguments(_, _, pos)
+            | EvalError::RecursionLimit(pos)
+            | EvalError::NestingLimit(pos) => pos,
+        }
+    }
+
+    /// Moves the position of the error to `pos`, for errors in the body of a
+    /// function, which is not on the line being evaluated.
+    fn at(mut self, pos: usize) -> Self {
+        *self.pos() = pos;
+        self
+    }
+}
+
+/// Deep enough for any sensible recursion, shallow enough for the stack.
+const MAX_CALL_DEPTH: usize = 256;
+/// Bounds the recursion of `eval_expr` through nested calls, as each of them
+/// can go `ast::MAX_HEIGHT` deeper.
+const MAX_NESTING: usize = 2 * ast::MAX_HEIGHT;
+
+/// The arguments of the function being evaluated.
+struct Frame<'a, N> {
+    params: &'a [String],
+    args: &'a [N],
+    /// How many calls deep.
+    depth: usize,
+    /// How many subexpressions deep, through the calls.
+    nesting: usize,
+}
+
+impl<N> Frame<'_, N> {
+    /// The frame of the line being evaluated, outside of any function.
+    fn top_level() -> Self {
+        Frame {
+            params: &[],
+            args: &[],
+            depth: 0,
+            nesting: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+fn eval_postfix<N: Number>(
+    postfix: SeqList<Token<N>>,
+    env: &Environment<N>,
+) -> Result<N, EvalError<N>> {
+    eval_expr(&Expr::from_postfix(&postfix)?, env, &Frame::top_level())
+}
+
+/// Evaluates `expr`, leaving alone the operands that `&&`, `||` or a
+/// conditional turn out not to need.
+fn eval_expr<N: Number>(
+    expr: &Expr<N>,
+    env: &Environment<N>,
+    frame: &Frame<N>,
+) -> Result<N, EvalError<N>> {
+    // Only a call goes deeper than `ast::MAX_HEIGHT`, and it moves the error
+    // to where it is called.
+    if frame.nesting == MAX_NESTING {
+        return Err(EvalError::RecursionLimit(0));
+    }
+    let inner = Frame {
+        params: frame.params,
+        args: frame.args,
+        depth: frame.depth,
+        nesting: frame.nesting + 1,
+    };
+    let eval = |expr| eval_expr(expr, env, &inner);
+    let supported = |op: &Operator, pos: usize| {
+        if N::supports(op) {
+            Ok(())
+        } else {
+            Err(EvalError::Unsupported(op.clone(), pos))
+        }
+    };
+    match expr {
+        Expr::Num(n) => Ok(n.clone()),
+        Expr::Variable(name, pos) => frame
+            .params
+            .iter()
+            .position(|param| param == name)
+            .map(|i| &frame.args[i])
+            .or_else(|| env.get(name))
+            .cloned()
+            .ok_or_else(|| EvalError::UnknownVariable(name.clone(), *pos)),
+        Expr::Unary(op, operand, pos) => {
+            let operand = eval(operand)?;
+            supported(op, *pos)?;
+            number::apply_unary(op, &operand)
+                .ok_or_else(|| EvalError::UnaryOverflow(op.clone(), operand, *pos))
+        }
+        Expr::Binary(op @ (Operator::And | Operator::Or), operand1, operand2, _) => {
+            let left = eval(operand1)?.is_true();
+            // A false left operand decides `&&`, and a true one `||`.
+            if left == (*op == Operator::Or) {
+                return Ok(N::from_bool(left));
+            }
+            Ok(N::from_bool(eval(operand2)?.is_true()))
+        }
+        Expr::Binary(op, operand1, operand2, pos) => {
+            let operand1 = eval(operand1)?;
+            let operand2 = eval(operand2)?;
+            supported(op, *pos)?;
+            number::apply(op, &operand1, &operand2)
+                .ok_or_else(|| EvalError::Overflow(op.clone(), operand1, operand2, *pos))
+        }
+        Expr::Conditional(condition, operand1, operand2) => {
+            if eval(condition)?.is_true() {
+                eval(operand1)
+            } else {
+                eval(operand2)
+            }
+        }
+        Expr::Call(name, args, pos) => {
+            let args = args.iter().map(eval).collect::<Result<Vec<_>, _>>()?;
+            call(name, &args, *pos, env, &inner)
+        }
+    }
+}
+
+/// Calls the function `name` at `pos`, from `frame`.
+fn call<N: Number>(
+    name: &str,
+    args: &[N],
+    pos: usize,
+    env: &Environment<N>,
+    frame: &Frame<N>,
+) -> Result<N, EvalError<N>> {
+    let wrong_arity = |expected| EvalError::WrongArity {
+        name: name.to_owned(),
+        expected,
+        found: args.len(),
+        pos,
+    };
+    if let Some(function) = env.function(name) {
+        if args.len() != function.params.len() {
+            return Err(wrong_arity(Arity::Exactly(function.params.len())));
+        }
+        if frame.depth == MAX_CALL_DEPTH {
+            return Err(EvalError::RecursionLimit(pos));
+        }
+        let frame = Frame {
+            params: &function.params,
+            args,
+            depth: frame.depth + 1,
+            nesting: frame.nesting,
+        };
+        return eval_expr(&function.body, env, &frame).map_err(|e| e.at(pos));
+    }
+    let builtin = Builtin::from_name(name)
+        .filter(N::supports_builtin)
+        .ok_or_else(|| EvalError::UnknownFunction(name.to_owned(), pos))?;
+    if !builtin.arity().accepts(args.len()) {
+        return Err(wrong_arity(builtin.arity()));
+    }
+    builtin
+        .call(args)
+        .ok_or_else(|| EvalError::InvalidArguments(builtin, args.to_vec(), pos))
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn test() {
+        let test_cases: Vec<(_, _, Option<i32>)> = vec![
+            (
+                "1 +1",
+                Ok(vec![
+                    Token::Num(1, 0),
+                    Token::Num(1, 3),
+                    Token::Operator(Operator::Add, 2),
+                ]),
+                Some(2),
+            ),
+            (
+                "$",
+                Err(vec![ExprError {
+                    what: ExprErrorType::IllegalChar,
+                    pos: 0,
+                }]),
+                None,
+            ),
+            (
+                "1+2*3",
+                Ok(vec![
+                    Token::Num(1, 0),
+                    Token::Num(2, 2),
+                    Token::Num(3, 4),
+                    Token::Operator(Operator::Multiply, 3),
+                    Token::Operator(Operator::Add, 1),
+                ]),
+                Some(7),
+            ),
+            (
+                "(( 1 + 2 ) * 3)",
+                Ok(vec![
+                    Token::Num(1, 3),
+                    Token::Num(2, 7),
+                    Token::Operator(Operator::Add, 5),
+                    Token::Num(3, 13),
+                    Token::Operator(Operator::Multiply, 11),
+                ]),
+                Some(9),
+            ),
+            (
+                "(1+1",
+                Err(vec![ExprError {
+                    what: ExprErrorType::UnmatchedParenthesis,
+                    pos: 0,
+                }]),
+                None,
+            ),
+            (
+                "1+1)",
+                Err(vec![ExprError {
+                    what: ExprErrorType::UnmatchedParenthesis,
+                    pos: 3,
+                }]),
+                None,
+            ),
+            (
+                "1 + 1 + 1",
+                Ok(vec![
+                    Token::Num(1, 0),
+                    Token::Num(1, 4),
+                    Token::Operator(Operator::Add, 2),
+                    Token::Num(1, 8),
+                    Token::Operator(Operator::Add, 6),
+                ]),
+                Some(3),
+            ),
+            (
+                "1 - (2 + 3)",
+                Ok(vec![
+                    Token::Num(1, 0),
+                    Token::Num(2, 5),
+                    Token::Num(3, 9),
+                    Token::Operator(Operator::Add, 7),
+                    Token::Operator(Operator::Subtract, 2),
+                ]),
+                Some(-4),
+            ),
+            (
+                "-3 + 4",
+                Ok(vec![
+                    Token::Num(3, 1),
+                    Token::Operator(Operator::Negate, 0),
+                    Token::Num(4, 5),
+                    Token::Operator(Operator::Add, 3),
+                ]),
+                Some(1),
+            ),
+            (
+                "2 * (-1)",
+                Ok(vec![
+                    Token::Num(2, 0),
+                    Token::Num(1, 6),
+                    Token::Operator(Operator::Negate, 5),
+                    Token::Operator(Operator::Multiply, 2),
+                ]),
+                Some(-2),
+            ),
+            (
+                "1 - -+-2",
+                Ok(vec![
+                    Token::Num(1, 0),
+                    Token::Num(2, 7),
+                    Token::Operator(Operator::Negate, 6),
+                    Token::Operator(Operator::Plus, 5),
+                    Token::Operator(Operator::Negate, 4),
+                    Token::Operator(Operator::Subtract, 2),
+                ]),
+                Some(-1),
+            ),
+            (
+                "-2 * 3",
+                Ok(vec![
+                    Token::Num(2, 1),
+                    Token::Operator(Operator::Negate, 0),
+                    Token::Num(3, 5),
+                    Token::Operator(Operator::Multiply, 3),
+                ]),
+                Some(-6),
+            ),
+            (
+                "2 ** 3 ** 2",
+                Ok(vec![
+                    Token::Num(2, 0),
+                    Token::Num(3, 5),
+                    Token::Num(2, 10),
+                    Token::Operator(Operator::Power, 7),
+                    Token::Operator(Operator::Power, 2),
+                ]),
+                Some(512),
+            ),
+            (
+                "-2**2",
+                Ok(vec![
+                    Token::Num(2, 1),
+                    Token::Num(2, 4),
+                    Token::Operator(Operator::Power, 2),
+                    Token::Operator(Operator::Negate, 0),
+                ]),
+                Some(-4),
+            ),
+            (
+                "2 ** -1",
+                Ok(vec![
+                    Token::Num(2, 0),
+                    Token::Num(1, 6),
+                    Token::Operator(Operator::Negate, 5),
+                    Token::Operator(Operator::Power, 2),
+                ]),
+                None,
+            ),
+            (
+                "-7 // 2 * 10 + -7 / 2 + 7 % -2",
+                Ok(vec![
+                    Token::Num(7, 1),
+                    Token::Operator(Operator::Negate, 0),
+                    Token::Num(2, 6),
+                    Token::Operator(Operator::FloorDivide, 3),
+                    Token::Num(10, 10),
+                    Token::Operator(Operator::Multiply, 8),
+                    Token::Num(7, 16),
+                    Token::Operator(Operator::Negate, 15),
+                    Token::Num(2, 20),
+                    Token::Operator(Operator::Divide, 18),
+                    Token::Operator(Operator::Add, 13),
+                    Token::Num(7, 24),
+                    Token::Num(2, 29),
+                    Token::Operator(Operator::Negate, 28),
+                    Token::Operator(Operator::Remainder, 26),
+                    Token::Operator(Operator::Add, 22),
+                ]),
+                Some(-42),
+            ),
+            (
+                "1 | 6 & 3 ^ 1 << 1 + 1",
+                Ok(vec![
+                    Token::Num(1, 0),
+                    Token::Num(6, 4),
+                    Token::Num(3, 8),
+                    Token::Operator(Operator::BitAnd, 6),
+                    Token::Num(1, 12),
+                    Token::Num(1, 17),
+                    Token::Num(1, 21),
+                    Token::Operator(Operator::Add, 19),
+                    Token::Operator(Operator::ShiftLeft, 14),
+                    Token::Operator(Operator::BitXor, 10),
+                    Token::Operator(Operator::BitOr, 2),
+                ]),
+                Some(7),
+            ),
+            (
+                "1 >> 2 < 3",
+                Ok(vec![
+                    Token::Num(1, 0),
+                    Token::Num(2, 5),
+                    Token::Operator(Operator::ShiftRight, 2),
+                    Token::Num(3, 9),
+                    Token::Operator(Operator::Less, 7),
+                ]),
+                Some(1),
+            ),
+            (
+                "1 >> 2 = 3",
+                Err(vec![ExprError {
+                    what: ExprErrorType::IllegalChar,
+                    pos: 7,
+                }]),
+                None,
+            ),
+            (
+                " 1000000000000000000",
+                Err(vec![ExprError {
+                    what: ExprErrorType::I32Overflow,
+                    pos: 1,
+                }]),
+                None,
+            ),
+        ];
+        let mut i = test_cases
+            .iter()
+            .map(|(expr, expect, result)| {
+                let postfix = infix_to_postfix(expr);
+                let mut is_good = match expect {
+                    Ok(expect) => {
+                        postfix.is_ok() && postfix.as_ref().unwrap().into_iter().eq(expect.iter())
+                    }
+                    Err(e) => postfix.is_err() && postfix.as_ref().err().unwrap() == e,
+                };
+                if !is_good {
+                    println!("Expression:");
+                    println!("{}", expr);
+                    println!("Got:");
+                    println!("{:?}", postfix);
+                    println!("Expect:");
+                    println!("{:?}", expect)
+                }
+                if result.is_some() {
+                    let eval_result = eval_postfix(postfix.unwrap(), &Environment::new()).ok();
+                    is_good = &eval_result == result;
+                    if !is_good {
+                        println!("{} (should be {})", eval_result.unwrap(), result.unwrap())
+                    }
+                }
+                is_good
+            })
+            .filter(|b| !b);
+        assert!(i.next().is_none())
+    }
+
+    #[test]
+    fn test_get_number() {
+        let mut i = "123a ".chars().enumerate().peekable();
+        while let Some((_, c)) = i.next() {
+            match c {
+                '0'..='9' => {
+                    let number = get_number::<i32, _>(c, 0, &mut i).unwrap();
+                    assert_eq!(number, 123);
+                    assert_eq!(i.next().unwrap().1, 'a')
+                }
+                _ => {
+                    i.next();
+                }
+            }
+        }
+
+        for (s, number, rest) in [
+            ("1.5e3+", 1500.0, "+"),
+            ("2e+", 2.0, "e+"),
+            ("3.E1", 30.0, ""),
+        ] {
+            let mut i = s.chars().enumerate().peekable();
+            let c = i.next().unwrap().1;
+            assert_eq!(get_number::<f64, _>(c, 0, &mut i), Ok(number));
+            assert!(i.map(|(_, c)| c).eq(rest.chars()));
+        }
+    }
+
+    #[test]
+    fn test_variables() {
+        let mut env = Environment::new();
+        let mut eval_line =
+            |statement| eval_and_print::<i32>(statement, &mut env, &Args::default());
+        assert_eq!(eval_line("let x = 3 * 4"), None);
+        assert_eq!(eval_line("x + 1"), Some(Value::Number(13)));
+        assert_eq!(eval_line("_ * 2"), Some(Value::Number(26)));
+        assert_eq!(eval_line("  y=ans-x"), None);
+        assert_eq!(eval_line("x = y"), None);
+        assert_eq!(eval_line("x + _"), Some(Value::Number(40)));
+        assert_eq!(eval_line("x + z"), None);
+        assert_eq!(eval_line("ans"), Some(Value::Number(40)));
+
+        let target = |s| get_assignment_target(s).map_err(|e| (e.what, e.pos));
+        let variable = |name: &str| Some(Target::Variable(name.to_owned()));
+        assert_eq!(target("let x1 = 1"), Ok((variable("x1"), 8)));
+        assert_eq!(target("x == 1"), Ok((None, 0)));
+        assert_eq!(target("letx = 1"), Ok((variable("letx"), 6)));
+        assert_eq!(target("let"), Err((ExprErrorType::InvalidAssignment, 3)));
+        assert_eq!(
+            target("let x + 1"),
+            Err((ExprErrorType::InvalidAssignment, 6))
+        );
+        assert_eq!(
+            target("let ans = 1"),
+            Err((ExprErrorType::ReadOnlyVariable, 4))
+        );
+
+        assert_eq!(
+            eval_postfix(
+                infix_to_postfix::<i32>("1 + y").unwrap(),
+                &Environment::new()
+            )
+            .err()
+            .map(|e| format!("{:?}", e)),
+            Some("UnknownVariable(\"y\", 4)".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_functions() {
+        assert_eq!(
+            infix_to_postfix::<i32>("max(1, -f(), 2 * g(3)) + 1").map(|p| p.to_vec()),
+            Ok(vec![
+                Token::Num(1, 4),
+                Token::Call("f".to_owned(), 0, 8),
+                Token::Operator(Operator::Negate, 7),
+                Token::Num(2, 13),
+                Token::Num(3, 19),
+                Token::Call("g".to_owned(), 1, 17),
+                Token::Operator(Operator::Multiply, 15),
+                Token::Call("max".to_owned(), 3, 0),
+                Token::Num(1, 25),
+                Token::Operator(Operator::Add, 23),
+            ])
+        );
+        assert_eq!(
+            infix_to_postfix::<i32>("(1, 2)").err(),
+            Some(vec![ExprError {
+                what: ExprErrorType::UnexpectedComma,
+                pos: 2
+            }])
+        );
+        assert_eq!(
+            infix_to_postfix::<i32>("1 + abs (2").err(),
+            Some(vec![ExprError {
+                what: ExprErrorType::UnmatchedParenthesis,
+                pos: 8
+            }])
+        );
+
+        assert_eq!(eval::<i32>("abs(-3) + max(1, 7, 2) * min(4)"), Ok(31));
+        assert_eq!(eval::<i32>("gcd(-12, 18) + pow(2, 3)"), Ok(14));
+        assert_eq!(eval::<f64>("sqrt(2) ** 2"), Ok(2_f64.sqrt().powi(2)));
+        assert!(eval::<f64>("sqrt(-1)").is_err());
+        assert_eq!(
+            eval::<BigRational>("gcd(2 ** 80, 6 ** 40)").map(|r| r.to_string()),
+            Ok((1_u64 << 40).to_string())
+        );
+        let error = |expression| {
+            let postfix = infix_to_postfix::<i32>(expression).unwrap();
+            let e = eval_postfix(postfix, &Environment::new()).err()?;
+            let e = e.into_expr_error();
+            Some((e.what.to_string(), e.pos))
+        };
+        assert_eq!(
+            error("1 + sqrt(4)"),
+            Some(("unknown function `sqrt`".to_owned(), 4))
+        );
+        assert_eq!(
+            error("abs(1, 2)"),
+            Some(("`abs` takes 1 argument(s) but got 2".to_owned(), 0))
+        );
+        assert_eq!(
+            error("2 * max()"),
+            Some(("`max` takes at least 1 argument(s) but got 0".to_owned(), 4))
+        );
+
+        let mut env = Environment::new();
+        let mut eval_line =
+            |statement| eval_and_print::<i32>(statement, &mut env, &Args::default());
+        assert_eq!(eval_line("let y = 10"), None);
+        assert_eq!(eval_line("fn f(x, z) = x * z + y"), None);
+        assert_eq!(eval_line("f(2, 3)"), Some(Value::Number(16)));
+        assert_eq!(eval_line("y = 0"), None);
+        assert_eq!(eval_line("fn g() = f(ans, 2) + x"), None);
+        assert_eq!(eval_line("fn g() = f(ans, 2)"), None);
+        assert_eq!(eval_line("g() + f(1, 1)"), Some(Value::Number(33)));
+        assert_eq!(eval_line("fn loop(n) = loop(n)"), None);
+        assert_eq!(eval_line("loop(1)"), None);
+
+        let target = |s| get_assignment_target(s).map_err(|e| (e.what, e.pos));
+        assert_eq!(
+            target("fn f ( a,b ) =a"),
+            Ok((
+                Some(Target::Function(
+                    "f".to_owned(),
+                    vec!["a".to_owned(), "b".to_owned()]
+                )),
+                14
+            ))
+        );
+        assert_eq!(
+            target("fn f() = 1"),
+            Ok((Some(Target::Function("f".to_owned(), vec![])), 8))
+        );
+        assert_eq!(
+            target("fn f(a, a) = 1"),
+            Err((ExprErrorType::InvalidDefinition, 8))
+        );
+        assert_eq!(
+            target("fn f(a,) = 1"),
+            Err((ExprErrorType::InvalidDefinition, 7))
+        );
+        assert_eq!(
+            target("fn f(a) 1"),
+            Err((ExprErrorType::InvalidDefinition, 8))
+        );
+        assert_eq!(
+            target("fn min(a) = a"),
+            Err((ExprErrorType::BuiltinRedefinition, 3))
+        );
+    }
+
+    #[test]
+    fn test_logic() {
+        assert_eq!(eval::<i32>("1 < 2 && 2 <= 2 || 0"), Ok(1));
+        assert_eq!(eval::<i32>("!0 + !5 + (3 != 3) + (4 >= 5)"), Ok(1));
+        assert_eq!(eval::<i32>("2 & 3 == 3"), Ok(0));
+        assert_eq!(eval::<f64>("3 == 3.0 && 0.5 > 0.25"), Ok(1.0));
+        // Only the operands needed are evaluated.
+        assert_eq!(eval::<i32>("0 && 1 / 0"), Ok(0));
+        assert_eq!(eval::<i32>("2 || x"), Ok(1));
+        assert_eq!(eval::<i32>("1 ? 2 : 1 / 0"), Ok(2));
+        assert_eq!(eval::<i32>("0 ? 1 : 0 ? 2 : 3"), Ok(3));
+        assert_eq!(eval::<i32>("1 ? 0 ? 4 : 5 : 6"), Ok(5));
+        assert_eq!(eval::<i32>("1 + 1 == 2 ? 10 : 20"), Ok(10));
+
+        assert_eq!(
+            infix_to_postfix::<i32>("a ? b : c || d").map(|p| p.to_vec()),
+            Ok(vec![
+                Token::Variable("a".to_owned(), 0),
+                Token::Variable("b".to_owned(), 4),
+                Token::Variable("c".to_owned(), 8),
+                Token::Variable("d".to_owned(), 13),
+                Token::Operator(Operator::Or, 10),
+                Token::Operator(Operator::Conditional, 2),
+            ])
+        );
+        for (expression, what, pos) in [
+            ("1 ? 2", ExprErrorType::MissingColon, 2),
+            ("1 : 2", ExprErrorType::UnexpectedColon, 2),
+            ("1 ? 2 : 3 : 4", ExprErrorType::UnexpectedColon, 10),
+        ] {
+            assert_eq!(
+                infix_to_postfix::<i32>(expression).err(),
+                Some(vec![ExprError { what, pos }])
+            );
+        }
+
+        let mut env = Environment::new();
+        let mut eval_line =
+            |statement| eval_and_print::<i32>(statement, &mut env, &Args::default());
+        assert_eq!(eval_line("1 < 2"), Some(Value::Boolean(true)));
+        assert_eq!(eval_line("ans + 1"), Some(Value::Number(2)));
+        assert_eq!(
+            eval_line("ans ? !ans : 1 == 1"),
+            Some(Value::Boolean(false))
+        );
+        assert_eq!(eval_line("ans ? 1 : 2"), Some(Value::Number(2)));
+        assert_eq!(eval_line("fn fact(n) = n <= 1 ? 1 : n * fact(n - 1)"), None);
+        assert_eq!(eval_line("fact(10)"), Some(Value::Number(3_628_800)));
+        assert_eq!(eval_line("fn down(n) = n ? down(n - 1) : 0"), None);
+        assert_eq!(eval_line("down(100)"), Some(Value::Number(0)));
+    }
+
+    fn with_stack<T: Send + 'static>(f: impl FnOnce() -> T + Send + 'static) -> T {
+        let thread = thread::Builder::new().stack_size(STACK_SIZE).spawn(f);
+        thread.unwrap().join().unwrap()
+    }
+
+    #[test]
+    fn test_errors() {
+        let errors = |expression| {
+            infix_to_postfix::<i32>(expression)
+                .err()
+                .map(|errors| errors.into_iter().map(|e| (e.what, e.pos)).collect())
+        };
+        assert_eq!(
+            errors("1 $ 2 # (3"),
+            Some(vec![
+                (ExprErrorType::IllegalChar, 2),
+                (ExprErrorType::IllegalChar, 6),
+                (ExprErrorType::UnmatchedParenthesis, 8)
+            ])
+        );
+        assert_eq!(
+            errors("(1 ? 2) : 3"),
+            Some(vec![
+                (ExprErrorType::MissingColon, 3),
+                (ExprErrorType::UnexpectedColon, 8)
+            ])
+        );
+        assert_eq!(
+            errors("0b102 + 0xZZ + 9999999999"),
+            Some(vec![
+                (ExprErrorType::MalformedNumber, 4),
+                (ExprErrorType::MalformedNumber, 10),
+                (ExprErrorType::I32Overflow, 15)
+            ])
+        );
+        assert_eq!(
+            errors("1 2 + 3 (4) + 2x"),
+            Some(vec![
+                (ExprErrorType::UnexpectedOperand, 2),
+                (ExprErrorType::UnexpectedOperand, 8),
+                (ExprErrorType::UnexpectedOperand, 15)
+            ])
+        );
+        assert_eq!(
+            errors("1 + * 2 ? : 3 == -!1 <"),
+            Some(vec![
+                (ExprErrorType::UnexpectedOperator, 4),
+                (ExprErrorType::UnexpectedOperator, 10),
+                (ExprErrorType::MissingOperand, 22)
+            ])
+        );
+        assert_eq!(
+            errors("f() + (() * 2)"),
+            Some(vec![(ExprErrorType::EmptyParentheses, 7)])
+        );
+        assert_eq!(
+            errors("(1 +) + max(1,) + f(,)"),
+            Some(vec![
+                (ExprErrorType::MissingOperand, 4),
+                (ExprErrorType::MissingOperand, 14),
+                (ExprErrorType::MissingOperand, 20),
+                (ExprErrorType::MissingOperand, 21)
+            ])
+        );
+        assert_eq!(
+            errors("1 +"),
+            Some(vec![(ExprErrorType::MissingOperand, 3)])
+        );
+        assert_eq!(errors(" "), Some(vec![(ExprErrorType::MissingOperand, 1)]));
+        assert_eq!(errors("1 + $"), Some(vec![(ExprErrorType::IllegalChar, 4)]));
+
+        let error = |expression| {
+            let postfix = infix_to_postfix::<i32>(expression).unwrap();
+            let e = eval_postfix(postfix, &Environment::new()).err()?;
+            let e = e.into_expr_error();
+            Some((e.what.to_string(), e.pos))
+        };
+        assert_eq!(
+            error("2 * (1 / 0)"),
+            Some(("`1 / 0` is undefined or out of range".to_owned(), 7))
+        );
+        // As deep as `main` allows, which takes more than a test's stack.
+        let (flat, nested) = with_stack(|| {
+            let eval = |expression: String| {
+                let postfix = infix_to_postfix::<i32>(&expression).unwrap();
+                eval_postfix(postfix, &Environment::new()).map_err(|e| {
+                    let e = e.into_expr_error();
+                    (e.what, e.pos)
+                })
+            };
+            (
+                eval(format!("1{}", "+1".repeat(5000))),
+                eval(format!("{}1{}", "(1+".repeat(10_001), ")".repeat(10_001))),
+            )
+        });
+        assert_eq!(flat, Ok(5001));
+        assert_eq!(nested, Err((ExprErrorType::NestingLimit, 5)));
+        assert_eq!(abbreviate(i32::MIN), "-2147483648");
+        assert_eq!(
+            abbreviate("1234567890".repeat(5)),
+            "12345678901234567890...1234567890"
+        );
+
+        let mut env = Environment::new();
+        env.set("c".to_owned(), 1);
+        let postfix = infix_to_postfix::<i32>("a + b * c").unwrap();
+        assert_eq!(
+            check_variables(&postfix, &[], &env)
+                .map_err(|errors| errors.into_iter().map(|e| e.pos).collect::<Vec<_>>()),
+            Err(vec![0, 4])
+        );
+    }
+
+    #[test]
+    fn test_radix() {
+        assert_eq!(eval::<i32>("0xff + 0o17 - 0b1_01"), Ok(265));
+        assert_eq!(eval::<i32>("1_000_000 * 0X_10"), Ok(16_000_000));
+        assert_eq!(
+            eval::<i32>("0x80000000"),
+            Err("32-bit signed integer overflow".to_owned())
+        );
+        assert_eq!(eval::<f64>("0x10 / 0b100"), Ok(4.0));
+        assert_eq!(eval::<f64>("1_0.2_5e1_0"), Ok(10.25e10));
+        assert_eq!(
+            eval::<BigRational>("0xffff_ffff_ffff_ffff_ffff + 1").map(|r| r.to_string()),
+            Ok("1208925819614629174706176".to_owned())
+        );
+
+        for (expression, pos) in [("1 + 0xZZ", 6), ("0b102", 4), ("2 * 0x", 4), ("0o8", 2)] {
+            assert_eq!(
+                infix_to_postfix::<i32>(expression).err(),
+                Some(vec![ExprError {
+                    what: ExprErrorType::MalformedNumber,
+                    pos
+                }])
+            );
+        }
+
+        assert_eq!(number::to_string_radix(&-255, 16), Some("-0xff".to_owned()));
+        assert_eq!(number::to_string_radix(&5.0, 2), Some("0b101".to_owned()));
+        assert_eq!(number::to_string_radix(&0.5, 8), None);
+        assert_eq!(number::to_string_radix(&0.5, 10), Some("0.5".to_owned()));
+    }
+
+    fn eval<N: Number>(expression: &str) -> Result<N, String> {
+        let postfix = infix_to_postfix(expression).map_err(|e| e[0].what.to_string())?;
+        eval_postfix(postfix, &Environment::new()).map_err(|e| format!("{:?}", e))
+    }
+
+    #[test]
+    fn test_modes() {
+        assert_eq!(
+            eval::<i32>("1.5 * 2"),
+            Err("not an integer, try --float or --bigint".to_owned())
+        );
+        assert_eq!(
+            eval::<f64>("1.5e3 / 4 - -.5e1"),
+            Err("illegal character".to_owned())
+        );
+        assert_eq!(
+            eval::<f64>("1.5e3 / 4 + 2 ** 0.5 ** 2"),
+            Ok(375.0 + 2_f64.powf(0.25))
+        );
+        assert_eq!(
+            eval::<f64>("1 / 0"),
+            Err("Overflow(Divide, 1.0, 0.0, 2)".to_owned())
+        );
+        assert_eq!(
+            eval::<f64>("1 & 1"),
+            Err("Unsupported(BitAnd, 2)".to_owned())
+        );
+
+        let eval_ratio = |expression| eval::<BigRational>(expression).map(|r| r.to_string());
+        assert_eq!(eval_ratio("1 / 3 + 0.5"), Ok("5/6".to_owned()));
+        assert_eq!(
+            eval_ratio("2 ** 100 - 1"),
+            Ok("1267650600228229401496703205375".to_owned())
+        );
+        assert_eq!(eval_ratio("(2 ** 70 + 1) & 3"), Ok("1".to_owned()));
+        assert!(eval_ratio("1 / (1 - 1)").is_err());
+    }
+}
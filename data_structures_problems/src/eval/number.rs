@@ -0,0 +1,441 @@
+//! The kinds of numbers `eval` computes with: checked `i32`s by default,
+//! `f64`s with `--float` and exact rationals of big integers with `--bigint`.
+
+use std::fmt::{Debug, Display};
+
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_rational::BigRational;
+use num_traits::{FromPrimitive, Pow, Signed, ToPrimitive, Zero};
+
+use super::{functions::Builtin, ExprErrorType, Operator};
+
+pub trait Number: Clone + Debug + Display + PartialOrd {
+    /// Parses a literal of decimal digits, optionally followed by a fraction
+    /// and an exponent, like `1.5e3`.
+    fn parse(literal: &str) -> Result<Self, ExprErrorType>;
+
+    /// Parses the digits of an integer in `radix`.
+    fn from_digits(digits: &str, radix: u32) -> Result<Self, ExprErrorType>;
+
+    /// The value as an integer, if it is one.
+    fn to_integer(&self) -> Option<BigInt>;
+
+    fn from_i32(n: i32) -> Self;
+
+    fn from_bool(b: bool) -> Self {
+        Self::from_i32(b.into())
+    }
+
+    /// Anything but 0 is true.
+    fn is_true(&self) -> bool {
+        *self != Self::from_i32(0)
+    }
+
+    fn supports(_op: &Operator) -> bool {
+        true
+    }
+
+    /// Applies an arithmetic or bitwise operator. Returns `None` if the result
+    /// is undefined or out of range.
+    fn apply(op: &Operator, operand1: &Self, operand2: &Self) -> Option<Self>;
+
+    fn apply_unary(op: &Operator, operand: &Self) -> Option<Self>;
+
+    fn supports_builtin(builtin: &Builtin) -> bool {
+        *builtin != Builtin::Sqrt
+    }
+
+    fn abs(&self) -> Option<Self>;
+
+    fn gcd(&self, _other: &Self) -> Option<Self> {
+        None
+    }
+
+    fn sqrt(&self) -> Option<Self> {
+        None
+    }
+}
+
+impl Number for i32 {
+    fn parse(literal: &str) -> Result<Self, ExprErrorType> {
+        if literal.bytes().all(|b| b.is_ascii_digit()) {
+            literal.parse().map_err(|_| ExprErrorType::I32Overflow)
+        } else {
+            Err(ExprErrorType::NotAnInteger)
+        }
+    }
+
+    fn from_digits(digits: &str, radix: u32) -> Result<Self, ExprErrorType> {
+        i32::from_str_radix(digits, radix).map_err(|_| ExprErrorType::I32Overflow)
+    }
+
+    fn to_integer(&self) -> Option<BigInt> {
+        Some(BigInt::from(*self))
+    }
+
+    fn from_i32(n: i32) -> Self {
+        n
+    }
+
+    fn apply(op: &Operator, operand1: &Self, operand2: &Self) -> Option<Self> {
+        let f: fn(i32, i32) -> Option<i32> = match op {
+            Operator::Add => i32::checked_add,
+            Operator::Subtract => i32::checked_sub,
+            Operator::Multiply => i32::checked_mul,
+            Operator::Divide => i32::checked_div,
+            Operator::FloorDivide => floor_div,
+            Operator::Remainder => i32::checked_rem,
+            Operator::Power => |a, b| a.checked_pow(u32::try_from(b).ok()?),
+            Operator::BitAnd => |a, b| Some(a & b),
+            Operator::BitOr => |a, b| Some(a | b),
+            Operator::BitXor => |a, b| Some(a ^ b),
+            Operator::ShiftLeft => shift_left,
+            Operator::ShiftRight => |a, b| a.checked_shr(u32::try_from(b).ok()?),
+            _ => unreachable!("{:?} is not arithmetic", op),
+        };
+        f(*operand1, *operand2)
+    }
+
+    fn apply_unary(op: &Operator, operand: &Self) -> Option<Self> {
+        match op {
+            Operator::Negate => operand.checked_neg(),
+            Operator::Plus => Some(*operand),
+            _ => unreachable!("{:?} is binary", op),
+        }
+    }
+
+    fn abs(&self) -> Option<Self> {
+        self.checked_abs()
+    }
+
+    fn gcd(&self, other: &Self) -> Option<Self> {
+        let (mut a, mut b) = (self.unsigned_abs(), other.unsigned_abs());
+        while b != 0 {
+            (a, b) = (b, a % b);
+        }
+        i32::try_from(a).ok()
+    }
+}
+
+/// Unlike `checked_shl`, fails if bits are shifted out.
+fn shift_left(a: i32, b: i32) -> Option<i32> {
+    let shift = u32::try_from(b).ok()?;
+    let result = a.checked_shl(shift)?;
+    (result >> shift == a).then_some(result)
+}
+
+fn floor_div(a: i32, b: i32) -> Option<i32> {
+    let quotient = a.checked_div(b)?;
+    if a % b != 0 && (a < 0) != (b < 0) {
+        Some(quotient - 1)
+    } else {
+        Some(quotient)
+    }
+}
+
+impl Number for f64 {
+    fn parse(literal: &str) -> Result<Self, ExprErrorType> {
+        let n: f64 = literal.parse().map_err(|_| ExprErrorType::IllegalChar)?;
+        Some(n)
+            .filter(|n| n.is_finite())
+            .ok_or(ExprErrorType::NumberTooLarge)
+    }
+
+    fn from_digits(digits: &str, radix: u32) -> Result<Self, ExprErrorType> {
+        BigInt::parse_bytes(digits.as_bytes(), radix)
+            .and_then(|n| n.to_f64())
+            .filter(|n| n.is_finite())
+            .ok_or(ExprErrorType::NumberTooLarge)
+    }
+
+    fn to_integer(&self) -> Option<BigInt> {
+        BigInt::from_f64(*self).filter(|_| self.fract() == 0.0)
+    }
+
+    fn from_i32(n: i32) -> Self {
+        n.into()
+    }
+
+    fn supports(op: &Operator) -> bool {
+        !matches!(
+            op,
+            Operator::BitAnd
+                | Operator::BitOr
+                | Operator::BitXor
+                | Operator::ShiftLeft
+                | Operator::ShiftRight
+        )
+    }
+
+    fn apply(op: &Operator, operand1: &Self, operand2: &Self) -> Option<Self> {
+        let (a, b) = (*operand1, *operand2);
+        let result = match op {
+            Operator::Add => a + b,
+            Operator::Subtract => a - b,
+            Operator::Multiply => a * b,
+            Operator::Divide => a / b,
+            Operator::FloorDivide => (a / b).floor(),
+            Operator::Remainder => a % b,
+            Operator::Power => a.powf(b),
+            _ => return None,
+        };
+        // Like the integers, report infinities and NaNs instead of carrying
+        // them along.
+        Some(result).filter(|r| r.is_finite())
+    }
+
+    fn apply_unary(op: &Operator, operand: &Self) -> Option<Self> {
+        match op {
+            Operator::Negate => Some(-operand),
+            Operator::Plus => Some(*operand),
+            _ => unreachable!("{:?} is binary", op),
+        }
+    }
+
+    fn supports_builtin(builtin: &Builtin) -> bool {
+        *builtin != Builtin::Gcd
+    }
+
+    fn abs(&self) -> Option<Self> {
+        Some(f64::abs(*self))
+    }
+
+    fn sqrt(&self) -> Option<Self> {
+        Some(f64::sqrt(*self)).filter(|r| r.is_finite())
+    }
+}
+
+/// Bounds powers of ten in literals, exponents and shifts.
+const MAX_EXPONENT: u32 = 1 << 16;
+/// Bounds the size of powers and shifts, so that a short expression cannot
+/// ask for gigabytes of digits.
+const MAX_BITS: u64 = 1 << 20;
+
+fn small_exponent(exponent: &BigRational) -> Option<i32> {
+    let exponent = exponent.is_integer().then(|| exponent.to_integer())?;
+    exponent
+        .to_i32()
+        .filter(|e| e.unsigned_abs() <= MAX_EXPONENT)
+}
+
+fn to_integer(r: &BigRational) -> Option<BigInt> {
+    r.is_integer().then(|| r.to_integer())
+}
+
+impl Number for BigRational {
+    fn parse(literal: &str) -> Result<Self, ExprErrorType> {
+        let (mantissa, exponent) = match literal.split_once(['e', 'E']) {
+            Some((mantissa, exponent)) => (
+                mantissa,
+                exponent
+                    .parse::<i64>()
+                    .map_err(|_| ExprErrorType::NumberTooLarge)?,
+            ),
+            None => (literal, 0),
+        };
+        let (integral, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+        let digits = BigInt::parse_bytes(format!("{}{}", integral, fraction).as_bytes(), 10)
+            .ok_or(ExprErrorType::IllegalChar)?;
+        let scale = exponent
+            .checked_sub(fraction.len() as i64)
+            .filter(|scale| scale.unsigned_abs() <= MAX_EXPONENT as u64)
+            .ok_or(ExprErrorType::NumberTooLarge)?;
+        let ten = BigRational::from_integer(BigInt::from(10));
+        Ok(BigRational::from_integer(digits) * ten.pow(scale as i32))
+    }
+
+    fn from_digits(digits: &str, radix: u32) -> Result<Self, ExprErrorType> {
+        BigInt::parse_bytes(digits.as_bytes(), radix)
+            .map(BigRational::from_integer)
+            .ok_or(ExprErrorType::MalformedNumber)
+    }
+
+    fn to_integer(&self) -> Option<BigInt> {
+        to_integer(self)
+    }
+
+    fn from_i32(n: i32) -> Self {
+        BigRational::from_integer(n.into())
+    }
+
+    fn apply(op: &Operator, operand1: &Self, operand2: &Self) -> Option<Self> {
+        let (a, b) = (operand1, operand2);
+        let divisible = !b.is_zero();
+        Some(match op {
+            Operator::Add => a + b,
+            Operator::Subtract => a - b,
+            Operator::Multiply => a * b,
+            Operator::Divide if divisible => a / b,
+            Operator::FloorDivide if divisible => (a / b).floor(),
+            Operator::Remainder if divisible => a % b,
+            Operator::Power => {
+                let exponent = small_exponent(b)?;
+                if a.is_zero() && exponent < 0 {
+                    return None;
+                }
+                let bits = (a.numer().bits() + a.denom().bits()) * exponent.unsigned_abs() as u64;
+                if bits > MAX_BITS {
+                    return None;
+                }
+                a.pow(exponent)
+            }
+            Operator::BitAnd => BigRational::from_integer(to_integer(a)? & to_integer(b)?),
+            Operator::BitOr => BigRational::from_integer(to_integer(a)? | to_integer(b)?),
+            Operator::BitXor => BigRational::from_integer(to_integer(a)? ^ to_integer(b)?),
+            Operator::ShiftLeft | Operator::ShiftRight => {
+                let shift = usize::try_from(small_exponent(b)?).ok()?;
+                let a = to_integer(a)?;
+                BigRational::from_integer(match op {
+                    Operator::ShiftLeft if a.bits() + shift as u64 > MAX_BITS => return None,
+                    Operator::ShiftLeft => a << shift,
+                    _ => a >> shift,
+                })
+            }
+            Operator::Divide | Operator::FloorDivide | Operator::Remainder => return None,
+            _ => unreachable!("{:?} is not arithmetic", op),
+        })
+    }
+
+    fn apply_unary(op: &Operator, operand: &Self) -> Option<Self> {
+        match op {
+            Operator::Negate => Some(-operand),
+            Operator::Plus => Some(operand.clone()),
+            _ => unreachable!("{:?} is binary", op),
+        }
+    }
+
+    fn abs(&self) -> Option<Self> {
+        Some(Signed::abs(self))
+    }
+
+    /// Only of integers.
+    fn gcd(&self, other: &Self) -> Option<Self> {
+        Some(BigRational::from_integer(
+            to_integer(self)?.gcd(&to_integer(other)?),
+        ))
+    }
+}
+
+/// Applies `op`, where the comparisons and the logical operators work alike
+/// for every kind of number: their results are 1 for true and 0 for false.
+pub fn apply<N: Number>(op: &Operator, operand1: &N, operand2: &N) -> Option<N> {
+    let (a, b) = (operand1, operand2);
+    let truth = match op {
+        Operator::Equal => a == b,
+        Operator::NotEqual => a != b,
+        Operator::Less => a < b,
+        Operator::LessEqual => a <= b,
+        Operator::Greater => a > b,
+        Operator::GreaterEqual => a >= b,
+        Operator::And => a.is_true() && b.is_true(),
+        Operator::Or => a.is_true() || b.is_true(),
+        _ => return N::apply(op, a, b),
+    };
+    Some(N::from_bool(truth))
+}
+
+pub fn apply_unary<N: Number>(op: &Operator, operand: &N) -> Option<N> {
+    match op {
+        Operator::Not => Some(N::from_bool(!operand.is_true())),
+        _ => N::apply_unary(op, operand),
+    }
+}
+
+/// Formats `n` in `radix`, with the prefix `eval` reads it back with if the
+/// radix is not 10. Only integers have such a form.
+pub fn to_string_radix<N: Number>(n: &N, radix: u32) -> Option<String> {
+    if radix == 10 {
+        return Some(n.to_string());
+    }
+    let n = n.to_integer()?;
+    let prefix = match radix {
+        16 => "0x",
+        8 => "0o",
+        2 => "0b",
+        _ => "",
+    };
+    let sign = if n.is_negative() { "-" } else { "" };
+    Some(format!(
+        "{}{}{}",
+        sign,
+        prefix,
+        n.magnitude().to_str_radix(radix)
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn ratio(numer: i64, denom: i64) -> BigRational {
+        BigRational::new(numer.into(), denom.into())
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(i32::parse("2147483647"), Ok(i32::MAX));
+        assert_eq!(i32::parse("2147483648"), Err(ExprErrorType::I32Overflow));
+        assert_eq!(i32::parse("1.5"), Err(ExprErrorType::NotAnInteger));
+
+        assert_eq!(f64::parse("1.5e3"), Ok(1500.0));
+        assert_eq!(f64::parse("2."), Ok(2.0));
+        assert_eq!(f64::parse("25E-2"), Ok(0.25));
+        assert_eq!(f64::parse("1e400"), Err(ExprErrorType::NumberTooLarge));
+
+        assert_eq!(BigRational::parse("1.5"), Ok(ratio(3, 2)));
+        assert_eq!(BigRational::parse("1.5e3"), Ok(ratio(1500, 1)));
+        assert_eq!(BigRational::parse("12e-3"), Ok(ratio(3, 250)));
+        assert_eq!(
+            BigRational::parse("1e100000"),
+            Err(ExprErrorType::NumberTooLarge)
+        );
+        assert_eq!(
+            BigRational::parse("1.5e-9223372036854775808"),
+            Err(ExprErrorType::NumberTooLarge)
+        );
+        let big = BigRational::parse("123456789012345678901234567890").unwrap();
+        assert_eq!(big.to_string(), "123456789012345678901234567890");
+    }
+
+    #[test]
+    fn test_apply() {
+        assert_eq!(i32::apply(&Operator::ShiftLeft, &1, &30), Some(1 << 30));
+        assert_eq!(i32::apply(&Operator::ShiftLeft, &1, &31), None);
+        assert_eq!(i32::apply(&Operator::ShiftLeft, &-1, &31), Some(i32::MIN));
+        assert_eq!(i32::apply(&Operator::ShiftLeft, &3, &30), None);
+
+        assert_eq!(f64::apply(&Operator::Divide, &1.0, &4.0), Some(0.25));
+        assert_eq!(f64::apply(&Operator::Divide, &1.0, &0.0), None);
+        assert!(!f64::supports(&Operator::BitAnd));
+
+        let apply = |op, a, b| BigRational::apply(&op, &a, &b);
+        assert_eq!(
+            apply(Operator::Divide, ratio(1, 3), ratio(1, 6)),
+            Some(ratio(2, 1))
+        );
+        assert_eq!(apply(Operator::Divide, ratio(1, 3), ratio(0, 1)), None);
+        assert_eq!(
+            apply(Operator::FloorDivide, ratio(-7, 1), ratio(2, 1)),
+            Some(ratio(-4, 1))
+        );
+        assert_eq!(
+            apply(Operator::Power, ratio(2, 3), ratio(-2, 1)),
+            Some(ratio(9, 4))
+        );
+        assert_eq!(apply(Operator::Power, ratio(2, 1), ratio(1, 2)), None);
+        assert_eq!(
+            apply(Operator::ShiftLeft, ratio(1, 1), ratio(100, 1))
+                .unwrap()
+                .to_string(),
+            "1267650600228229401496703205376"
+        );
+        assert_eq!(apply(Operator::BitAnd, ratio(1, 2), ratio(1, 1)), None);
+
+        let huge = apply(Operator::Power, ratio(10, 1), ratio(65536, 1)).unwrap();
+        assert_eq!(apply(Operator::Power, huge.clone(), ratio(65536, 1)), None);
+        let huger = huge.pow(5_i32);
+        assert_eq!(apply(Operator::ShiftLeft, huger.clone(), ratio(1, 1)), None);
+        assert!(apply(Operator::ShiftRight, huger, ratio(1, 1)).is_some());
+    }
+}
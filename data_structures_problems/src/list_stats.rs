@@ -1,6 +1,9 @@
 use data_structures::*;
+use rayon::prelude::*;
 use std::{
-    env, ops,
+    env,
+    fs::File,
+    io::{self, BufReader, BufWriter},
     process::exit,
     time::{Duration, Instant},
 };
@@ -15,6 +18,7 @@ fn main() {
             InputError => "Invalid input.".to_owned(),
             EmptyListError => "Empty list.".to_owned(),
             ArgsError(s) => format!("Invalid args: {}", s),
+            IoError(e) => format!("{}", e),
         };
         eprintln!("{}", msg);
         usage()
@@ -23,46 +27,91 @@ fn main() {
 
 fn usage() {
     println!(
-        "Usage: {} -i/--implementation <name> | -h/--help",
+        "Usage: {} -i/--implementation <name> [--load <snapshot>] [--dump <snapshot>] | -h/--help",
         env::args().next().unwrap()
     )
 }
 
-fn stats<T>() -> Result<()>
+fn read_list<T>(args: &Args) -> Result<T>
 where
     T: List<i32>,
     for<'b> &'b T: IntoIterator<Item = &'b i32>,
 {
-    let l = utils::read_numbers().ok_or(Error::InputError)?;
+    Ok(match (&args.load, &args.dump) {
+        (None, None) => utils::read_numbers().ok_or(Error::InputError)?,
+        (load, dump) => {
+            // Snapshots are of sequential lists only.
+            let numbers: SeqList<i32> = match load {
+                Some(path) => SeqList::read_from(BufReader::new(File::open(path)?))?,
+                None => utils::read_numbers::<i32, SeqList<i32>>().ok_or(Error::InputError)?,
+            };
+            if let Some(path) = dump {
+                numbers.write_to(BufWriter::new(File::create(path)?))?;
+            }
+            numbers.iter().copied().collect()
+        }
+    })
+}
+
+fn stats<T>(args: &Args) -> Result<()>
+where
+    T: List<i32>,
+    for<'b> &'b T: IntoIterator<Item = &'b i32>,
+{
+    let l: T = read_list(args)?;
     let stats = calc_stats(&l).ok_or(Error::EmptyListError)?;
     println!("{:?}", stats);
     Ok(())
 }
 
 fn actual_main() -> Result<()> {
-    let implementation = get_list_implementation()
-        .ok_or(Error::ArgsError("Specify the implementaion using `-i'."))?;
+    let args = get_args()?;
 
-    match implementation.as_ref() {
-        "sequential" => stats::<SeqList<i32>>(),
-        "singly_linked" => stats::<linked_list::LinkedList<i32>>(),
-        "circular" => stats::<cir_linked_list::CirLinkedList<i32>>(),
+    match args.implementation.as_ref() {
+        "sequential" => stats::<SeqList<i32>>(&args),
+        "singly_linked" => stats::<linked_list::LinkedList<i32>>(&args),
+        "circular" => stats::<cir_linked_list::CirLinkedList<i32>>(&args),
+        "slab" => stats::<slab_list::SlabList<i32>>(&args),
+        "parallel" => {
+            let l: SeqList<i32> = read_list(&args)?;
+            let stats = par_calc_stats(&l).ok_or(Error::EmptyListError)?;
+            println!("{:?}", stats);
+            Ok(())
+        }
         _ => Err(Error::ArgsError("No such list implementation.")),
     }
 }
 
-fn get_list_implementation() -> Option<String> {
-    let mut args = env::args();
-    args.next()?;
-    let option = args.next()?;
-    if option == "-i" || option == "--implementation" {
-        args.next()
-    } else if option == "-h" || option == "--help" {
-        usage();
-        exit(0)
-    } else {
-        None
+struct Args {
+    implementation: String,
+    load: Option<String>,
+    dump: Option<String>,
+}
+
+fn get_args() -> Result<Args> {
+    let mut implementation = None;
+    let mut load = None;
+    let mut dump = None;
+    let mut args = env::args().skip(1);
+    while let Some(option) = args.next() {
+        let value = match option.as_ref() {
+            "-i" | "--implementation" => &mut implementation,
+            "--load" => &mut load,
+            "--dump" => &mut dump,
+            "-h" | "--help" => {
+                usage();
+                exit(0)
+            }
+            _ => return Err(Error::ArgsError("Unknown option.")),
+        };
+        *value = Some(args.next().ok_or(Error::ArgsError("Missing a value."))?);
     }
+    Ok(Args {
+        implementation: implementation
+            .ok_or(Error::ArgsError("Specify the implementaion using `-i'."))?,
+        load,
+        dump,
+    })
 }
 
 #[derive(Debug)]
@@ -74,38 +123,46 @@ struct Stats<T> {
     time: Duration,
 }
 
+/// The min, the max, the sum and the count of some of the items, which
+/// combine in any order.
+type Partial<T> = (T, T, i64, usize);
+
+fn partial<T: Copy + Into<i64>>(i: T) -> Partial<T> {
+    (i, i, i.into(), 1)
+}
+
+fn combine<T: Ord>(
+    (min1, max1, sum1, len1): Partial<T>,
+    (min2, max2, sum2, len2): Partial<T>,
+) -> Partial<T> {
+    (min1.min(min2), max1.max(max2), sum1 + sum2, len1 + len2)
+}
+
+fn finish<T>((min, max, sum, len): Partial<T>, start: Instant) -> Stats<T> {
+    Stats {
+        min,
+        max,
+        avg: sum as f64 / len as f64,
+        time: start.elapsed(),
+    }
+}
+
 fn calc_stats<'a, T, L>(l: &'a L) -> Option<Stats<T>>
 where
-    T: 'a + Clone + Ord + ops::Add<&'a T, Output = T> + Into<f64>,
+    T: 'a + Copy + Ord + Into<i64>,
     L: List<T>,
     for<'b> &'b L: IntoIterator<Item = &'b T>,
 {
     let start = Instant::now();
-    let i = &mut l.into_iter();
-    let first_item = i.next()?;
-    let (min, max, sum, len) = i.fold(
-        (
-            first_item.clone(),
-            first_item.clone(),
-            first_item.clone(),
-            0_usize,
-        ),
-        |(min, max, sum, len), i: &'a T| {
-            let new_min;
-            let new_max;
-
-            new_min = std::cmp::min(i, &min);
-            new_max = std::cmp::max(i, &max);
-
-            (new_min.clone(), new_max.clone(), sum + i, len + 1)
-        },
-    );
-    Some(Stats {
-        min,
-        max,
-        avg: sum.into() / (len as f64),
-        time: start.elapsed(),
-    })
+    let stats = l.into_iter().map(|&i| partial(i)).reduce(combine)?;
+    Some(finish(stats, start))
+}
+
+/// Like `calc_stats` on a `SeqList`, but spread over all cores.
+fn par_calc_stats(l: &SeqList<i32>) -> Option<Stats<i32>> {
+    let start = Instant::now();
+    let stats = l.par_iter().map(|&i| partial(i)).reduce_with(combine)?;
+    Some(finish(stats, start))
 }
 
 type Result<T> = std::result::Result<T, Error>;
@@ -114,4 +171,33 @@ pub enum Error {
     EmptyListError,
     ArgsError(&'static str),
     InputError,
+    IoError(io::Error),
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::IoError(e)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_stats() {
+        let items = [3, i32::MAX, -7, i32::MAX, 5];
+        let l: SeqList<i32> = items.iter().copied().collect();
+        let linked: linked_list::LinkedList<i32> = items.iter().copied().collect();
+        let avg = items.iter().map(|&i| i as f64).sum::<f64>() / 5.0;
+        for stats in [
+            calc_stats(&l).unwrap(),
+            calc_stats(&linked).unwrap(),
+            par_calc_stats(&l).unwrap(),
+        ] {
+            assert_eq!((stats.min, stats.max, stats.avg), (-7, i32::MAX, avg));
+        }
+        assert!(calc_stats(&SeqList::<i32>::new()).is_none());
+        assert!(par_calc_stats(&SeqList::new()).is_none());
+    }
 }
@@ -1,10 +1,8 @@
 use std::{
     collections::{HashSet, VecDeque},
-    convert::identity,
     fmt::Display,
     io::{self, BufRead},
     str::SplitWhitespace,
-    usize,
 };
 
 use data_structures::{
@@ -29,7 +27,10 @@ fn main() {
     board[exit.0][exit.1] = Block::Exit;
     let maze = Maze { board, entry, exit };
     println!("{}", maze);
-    match (maze.solve_bfs(), maze.solve_dfs()) {
+    match (
+        maze.solve_bfs::<SeqQueue<Step>>(),
+        maze.solve_dfs::<SeqStack<Step>>(),
+    ) {
         (None, None) => {
             eprintln!("Unsolvable");
             std::process::exit(1)
@@ -137,19 +138,26 @@ impl Step {
 }
 
 impl Maze {
-    fn solve_dfs(&self) -> Option<Vec<Coord>> {
-        let stack = self.dfs()?;
+    fn solve_dfs<S>(&self) -> Option<Vec<Coord>>
+    where
+        S: Stack<Step>,
+        for<'a> &'a S: IntoIterator<Item = &'a Step>,
+    {
+        let stack: S = self.dfs()?;
         Some(
             stack
-                .list()
-                .iter()
+                .into_iter()
                 .map(|Step { coord, count: _ }| *coord)
                 .collect(),
         )
     }
 
-    fn dfs(&self) -> Option<SeqStack<Step>> {
-        let mut stack: SeqStack<Step> = SeqStack::new();
+    fn dfs<S>(&self) -> Option<S>
+    where
+        S: Stack<Step>,
+        for<'a> &'a S: IntoIterator<Item = &'a Step>,
+    {
+        let mut stack = S::new();
         let mut visited = HashSet::<Coord>::new();
         stack.push(Step::with_coord(self.entry));
         loop {
@@ -183,14 +191,11 @@ impl Maze {
     fn is_solved(&self, solution: Vec<Coord>) -> bool {
         assert!(solution[0] == self.entry);
         assert!(solution.last().unwrap() == &self.exit);
-        solution
-            .windows(2)
-            .map(|i| {
-                let a = i[0];
-                let b = i[1];
-                !matches!(self.board[a.0][a.1], Block::Obstacle) && is_adjacent(a, b)
-            })
-            .all(identity)
+        solution.windows(2).all(|i| {
+            let a = i[0];
+            let b = i[1];
+            !matches!(self.board[a.0][a.1], Block::Obstacle) && is_adjacent(a, b)
+        })
     }
 
     fn print_solution(&self, solution: &[Coord]) {
@@ -284,10 +289,14 @@ impl Orientation {
 }
 
 impl Maze {
-    fn solve_bfs(&self) -> Option<Vec<Coord>> {
+    fn solve_bfs<Q>(&self) -> Option<Vec<Coord>>
+    where
+        Q: Queue<Step>,
+        for<'a> &'a Q: IntoIterator<Item = &'a Step>,
+    {
         let mut prev_steps: Vec<Vec<Option<Coord>>> =
             vec![vec![None; self.board[0].len()]; self.board.len()];
-        let mut queue: SeqQueue<Step> = SeqQueue::new();
+        let mut queue = Q::new();
         queue.push(Step::with_coord(self.entry));
         prev_steps[self.entry.0][self.entry.1] = Some(self.entry);
         loop {
@@ -317,3 +326,50 @@ impl Maze {
         Some(Vec::from(solution))
     }
 }
+
+#[cfg(test)]
+mod test {
+    use data_structures::{queue::LinkedQueue, stack::LinkedStack};
+
+    use super::*;
+
+    fn maze(rows: &[&str], entry: Coord, exit: Coord) -> Maze {
+        let width = rows[0].len();
+        let mut board = Vec::new();
+        add_horizontal_boundary(&mut board, width);
+        for row in rows {
+            let mut vec = vec![Block::Obstacle];
+            vec.extend(row.chars().map(|c| match c {
+                '#' => Block::Obstacle,
+                _ => Block::Empty,
+            }));
+            vec.push(Block::Obstacle);
+            board.push(vec);
+        }
+        add_horizontal_boundary(&mut board, width);
+        board[entry.0][entry.1] = Block::Entry;
+        board[exit.0][exit.1] = Block::Exit;
+        Maze { board, entry, exit }
+    }
+
+    #[test]
+    fn test_implementations_agree() {
+        let mazes = [
+            maze(&["..#", "#..", "..."], (1, 1), (3, 3)),
+            maze(&["...", "##.", "..."], (1, 1), (3, 1)),
+            maze(&[".#.", "##.", "..."], (1, 1), (3, 3)),
+        ];
+        for maze in &mazes {
+            let bfs = maze.solve_bfs::<SeqQueue<Step>>();
+            assert_eq!(bfs, maze.solve_bfs::<LinkedQueue<Step>>());
+            let dfs = maze.solve_dfs::<SeqStack<Step>>();
+            assert_eq!(dfs, maze.solve_dfs::<LinkedStack<Step>>());
+            assert_eq!(bfs.is_some(), dfs.is_some());
+            if let (Some(bfs), Some(dfs)) = (bfs, dfs) {
+                assert!(maze.is_solved(bfs));
+                assert!(maze.is_solved(dfs));
+            }
+        }
+        assert!(mazes[2].solve_bfs::<SeqQueue<Step>>().is_none());
+    }
+}
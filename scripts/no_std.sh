@@ -0,0 +1,10 @@
+#! /usr/bin/sh
+# Checks that data_structures builds without std, with and without alloc.
+dir=$(cd -P -- "$(dirname -- "$0")" && pwd -P)
+cd "$dir"/../ || exit
+
+target=thumbv7em-none-eabihf
+rustup target add ${target} || exit
+cargo build -p data_structures --target ${target} --no-default-features || exit
+cargo build -p data_structures --target ${target} --no-default-features --features alloc || exit
+cargo build -p data_structures --target ${target} --no-default-features --features serde